@@ -1,4 +1,7 @@
-use std::{collections::HashSet, fs::read_to_string};
+mod render;
+
+use render::RenderOptions;
+use std::{collections::HashSet, env, fs::read_to_string};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum PipeDirection {
@@ -9,12 +12,13 @@ enum PipeDirection {
 }
 
 impl PipeDirection {
-    pub fn move_cursor(&self, x: usize, y: usize) -> (usize, usize) {
+    /// None when moving off the top or left edge, the other edges are caught by the grid lookup
+    pub fn move_cursor(&self, x: usize, y: usize) -> Option<(usize, usize)> {
         match self {
-            PipeDirection::North => (x - 1, y),
-            PipeDirection::East => (x, y + 1),
-            PipeDirection::South => (x + 1, y),
-            PipeDirection::West => (x, y - 1),
+            PipeDirection::North => Some((x.checked_sub(1)?, y)),
+            PipeDirection::East => Some((x, y + 1)),
+            PipeDirection::South => Some((x + 1, y)),
+            PipeDirection::West => Some((x, y.checked_sub(1)?)),
         }
     }
     pub fn flip(&self) -> Self {
@@ -33,7 +37,7 @@ struct PipeCell {
 }
 
 impl PipeCell {
-    pub fn from_char(value: &char) -> Self {
+    pub fn from_char(value: &char) -> Result<Self, String> {
         let directions = match value {
             '|' => [PipeDirection::North, PipeDirection::South],
             '-' => [PipeDirection::East, PipeDirection::West],
@@ -41,20 +45,32 @@ impl PipeCell {
             'J' => [PipeDirection::West, PipeDirection::North],
             '7' => [PipeDirection::West, PipeDirection::South],
             'F' => [PipeDirection::East, PipeDirection::South],
-            _ => panic!("Invalid pipe {}", value),
+            _ => return Err(format!("Invalid pipe {}", value)),
         };
-        Self {
+        Ok(Self {
             directions,
             symbol: *value,
-        }
+        })
     }
 
     pub fn from_dirs(directions: &[PipeDirection]) -> Self {
+        // Pick the symbol that has these connections, so the start looks like any other pipe
+        let symbol = ['|', '-', 'L', 'J', '7', 'F']
+            .into_iter()
+            .find(|c| {
+                Self::from_char(c)
+                    .is_ok_and(|cell| cell.connects(directions[0]) && cell.connects(directions[1]))
+            })
+            .unwrap();
         Self {
             directions: [directions[0], directions[1]],
-            symbol: 'S', // lazy hack
+            symbol,
         }
     }
+    /// Corners turn the loop, straight pipes just continue it
+    pub fn is_corner(&self) -> bool {
+        self.directions[0] != self.directions[1].flip()
+    }
     pub fn connects(&self, dir: PipeDirection) -> bool {
         dir == self.directions[0] || dir == self.directions[1]
     }
@@ -74,18 +90,62 @@ struct GridPoint {
 struct PipeGrid {
     grid: Vec<Vec<Option<PipeCell>>>,
     part_of_path: HashSet<GridPoint>,
+    /// The loop in walking order, starting at S
+    path: Vec<GridPoint>,
+}
 
-    start_x: usize,
-    start_y: usize,
+/// A pair of start connections that closes a loop back to S
+#[derive(Debug, Clone)]
+struct StartOption {
+    directions: [PipeDirection; 2],
+    path: Vec<GridPoint>,
+}
+
+fn cell_at(grid: &[Vec<Option<PipeCell>>], x: usize, y: usize) -> Option<PipeCell> {
+    grid.get(x)?.get(y).copied().flatten()
+}
+
+/// Follow the pipes out of the start one way, and check they come back in the other way
+fn trace_loop(
+    grid: &[Vec<Option<PipeCell>>],
+    start: GridPoint,
+    directions: [PipeDirection; 2],
+) -> Result<Vec<GridPoint>, String> {
+    let mut path = vec![start];
+    let mut direction = directions[0];
+    let mut cursor = start;
+    loop {
+        let (x, y) = direction
+            .move_cursor(cursor.x, cursor.y)
+            .ok_or_else(|| format!("Loop leaves the grid at {}/{}", cursor.x, cursor.y))?;
+        cursor = GridPoint { x, y };
+        if cursor == start {
+            if direction.flip() != directions[1] {
+                return Err(format!("Loop comes back into S going {:?}", direction));
+            }
+            return Ok(path);
+        }
+        let cell = cell_at(grid, x, y)
+            .ok_or_else(|| format!("Loop runs into ground or off the grid at {}/{}", x, y))?;
+        if !cell.connects(direction.flip()) {
+            return Err(format!(
+                "Pipe {} at {}/{} doesn't connect back",
+                cell.symbol, x, y
+            ));
+        }
+        direction = cell.get_next_dir(direction.flip());
+        path.push(cursor);
+    }
 }
 
 impl PipeGrid {
-    pub fn from_lines(lines: &[&str]) -> Self {
+    /// `start_choice` picks which loop to use when S could join more than one,
+    /// by default the longest is used
+    pub fn from_lines(lines: &[&str], start_choice: Option<usize>) -> Result<Self, String> {
         //
         let mut pipe_row: Vec<Option<PipeCell>> = Vec::new();
         let mut grid: Vec<Vec<Option<PipeCell>>> = Vec::new();
-        let mut cursor_x = 0;
-        let mut cursor_y = 0;
+        let mut start = None;
 
         for (row, line) in lines.iter().enumerate() {
             for (col, c) in line.chars().enumerate() {
@@ -94,141 +154,213 @@ impl PipeGrid {
                     pipe_row.push(None);
                 } else if c == 'S' {
                     //Start pos
-                    cursor_x = row;
-                    cursor_y = col;
+                    if start.is_some() {
+                        return Err(format!("Second start at {}/{}", row, col));
+                    }
+                    start = Some(GridPoint { x: row, y: col });
                     pipe_row.push(None);
                 } else {
-                    pipe_row.push(Some(PipeCell::from_char(&c)));
+                    let cell =
+                        PipeCell::from_char(&c).map_err(|e| format!("{} at {}/{}", e, row, col))?;
+                    pipe_row.push(Some(cell));
                 }
             }
             grid.push(pipe_row.clone());
             pipe_row.clear();
         }
-        //Have to now go back and re-write the start point so we know its directions
-        let mut start_dirs: Vec<PipeDirection> = Vec::new();
+        let start = start.ok_or("No start in grid")?;
 
-        if cursor_x > 0 {
-            //
-            if grid[cursor_x - 1][cursor_y].is_some_and(|p| p.connects(PipeDirection::South)) {
-                start_dirs.push(PipeDirection::North);
-            }
-        }
-        if cursor_y > 0 {
-            //
-            if grid[cursor_x][cursor_y - 1].is_some_and(|p| p.connects(PipeDirection::East)) {
-                start_dirs.push(PipeDirection::West);
-            }
+        //Have to now go back and re-write the start point so we know its directions
+        //Any neighbour that connects back to S is a candidate, rows may be ragged so every lookup is checked
+        let candidates: Vec<PipeDirection> = [
+            PipeDirection::North,
+            PipeDirection::West,
+            PipeDirection::South,
+            PipeDirection::East,
+        ]
+        .into_iter()
+        .filter(|dir| {
+            dir.move_cursor(start.x, start.y)
+                .and_then(|(x, y)| cell_at(&grid, x, y))
+                .is_some_and(|p| p.connects(dir.flip()))
+        })
+        .collect();
+        if candidates.len() < 2 {
+            return Err(format!(
+                "Start @ {}/{} only connects {:?}, it can't be on a loop",
+                start.x, start.y, candidates
+            ));
         }
-        if cursor_x < grid.len() {
-            //
-            if grid[cursor_x + 1][cursor_y].is_some_and(|p| p.connects(PipeDirection::North)) {
-                start_dirs.push(PipeDirection::South);
+
+        let mut options: Vec<StartOption> = Vec::new();
+        for (i, first) in candidates.iter().enumerate() {
+            for second in &candidates[i + 1..] {
+                let directions = [*first, *second];
+                // Pairs that don't close a loop just aren't options
+                if let Ok(path) = trace_loop(&grid, start, directions) {
+                    options.push(StartOption { directions, path });
+                }
             }
         }
-        if cursor_y < grid[0].len() {
-            //
-            if grid[cursor_x][cursor_y + 1].is_some_and(|p| p.connects(PipeDirection::West)) {
-                start_dirs.push(PipeDirection::East);
-            }
+        if options.is_empty() {
+            return Err(format!(
+                "Start @ {}/{} connects {:?} but no pair of them closes a loop",
+                start.x, start.y, candidates
+            ));
         }
-        if start_dirs.len() != 2 {
-            panic!("Bad start decode, start @ {}/{}", cursor_x, cursor_y);
-        } else {
+        let chosen = if candidates.len() == 2 {
             println!(
                 "Start decoded as {:?} @ {}/{}",
-                start_dirs, cursor_x, cursor_y
+                options[0].directions, start.x, start.y
             );
-        }
-        grid[cursor_x][cursor_y] = Some(PipeCell::from_dirs(&start_dirs));
+            0
+        } else {
+            println!(
+                "Ambiguous start @ {}/{}, it connects {:?} and can close {} loop(s):",
+                start.x,
+                start.y,
+                candidates,
+                options.len()
+            );
+            for (i, option) in options.iter().enumerate() {
+                println!(
+                    "  {}: {:?}, length {}",
+                    i,
+                    option.directions,
+                    option.path.len()
+                );
+            }
+            // Reversed so ties go to the first listed
+            let longest = (0..options.len())
+                .rev()
+                .max_by_key(|i| options[*i].path.len())
+                .unwrap();
+            let chosen = start_choice.unwrap_or(longest);
+            if chosen >= options.len() {
+                return Err(format!("No start loop {}", chosen));
+            }
+            println!("Using loop {}", chosen);
+            chosen
+        };
+        let StartOption { directions, path } = options.swap_remove(chosen);
+        grid[start.x][start.y] = Some(PipeCell::from_dirs(&directions));
 
-        Self {
+        Ok(Self {
             grid,
-            part_of_path: HashSet::new(),
-            start_x: cursor_x,
-            start_y: cursor_y,
-        }
+            part_of_path: path.iter().copied().collect(),
+            path,
+        })
+    }
+    pub fn get_loop_len(&self) -> usize {
+        self.path.len()
     }
-    pub fn get_loop_len(&mut self) -> usize {
-        //Starting at S, follow pipes until we get to the start
-        let mut cursor_x = self.start_x;
-        let mut cursor_y = self.start_y;
-        self.part_of_path.insert(GridPoint {
-            x: cursor_x,
-            y: cursor_y,
-        });
-
-        let mut direction = self.grid[cursor_x][cursor_y].unwrap().directions[0];
-        (cursor_x, cursor_y) = direction.move_cursor(cursor_x, cursor_y);
-
-        let mut steps = 1;
-        while cursor_x != self.start_x || cursor_y != self.start_y {
-            self.part_of_path.insert(GridPoint {
-                x: cursor_x,
-                y: cursor_y,
-            });
-            let cell = self.grid[cursor_x][cursor_y];
-            // println!(
-            //     "Step {},{} -> {:?} -> {:?}",
-            //     cursor_x, cursor_y, direction, cell
-            // );
-            direction = cell.unwrap().get_next_dir(direction.flip());
-            (cursor_x, cursor_y) = direction.move_cursor(cursor_x, cursor_y);
-            steps += 1;
+    /// Corners of the loop in walking order
+    pub fn get_loop_vertices(&self) -> Vec<GridPoint> {
+        self.path
+            .iter()
+            .filter(|p| self.grid[p.x][p.y].is_some_and(|c| c.is_corner()))
+            .copied()
+            .collect()
+    }
+    /// Tiles strictly inside the loop
+    /// The shoelace formula gives the loop's area through the tile centres,
+    /// then Pick's theorem (A = I + B/2 - 1) turns that into the interior tile count
+    pub fn count_enclosed(&self) -> usize {
+        let vertices = self.get_loop_vertices();
+        let mut twice_area: i64 = 0;
+        for (i, a) in vertices.iter().enumerate() {
+            let b = vertices[(i + 1) % vertices.len()];
+            twice_area += a.x as i64 * b.y as i64 - b.x as i64 * a.y as i64;
         }
-        steps
+        let boundary = self.path.len() as i64;
+        // I = A - B/2 + 1, doubled to stay in integers
+        ((twice_area.abs() - boundary + 2) / 2) as usize
     }
-    pub fn count_enclosed_nones(&mut self) {
+    /// Ray casting version of count_enclosed, kept as a cross-check
+    /// Rays run diagonally so they can only graze the loop at 'L' and '7' corners
+    pub fn count_enclosed_nones(&self) -> usize {
         let mut sum = 0;
         for (x, row) in self.grid.iter().enumerate() {
-            let mut row_print = "".to_owned();
-
             for (y, col) in row.iter().enumerate() {
-                let is_path_sample = self.part_of_path.contains(&GridPoint { x: x, y: y });
+                let is_path_sample = self.part_of_path.contains(&GridPoint { x, y });
                 if col.is_none() || !is_path_sample {
                     //Empty ground
                     //Count number of edges met from this point outwards on an angle that doesn't collide with edges
                     let mut edges_crossed = 0;
                     let mut x2 = x;
                     let mut y2 = y;
-                    while x2 < self.grid.len() && y2 < row.len() {
+                    while x2 < self.grid.len() && y2 < self.grid[x2].len() {
                         let sample = self.grid[x2][y2];
                         let is_in_path = self.part_of_path.contains(&GridPoint { x: x2, y: y2 });
-                        if is_in_path {
-                            if sample.is_some_and(|s| !(s.symbol == 'L' || s.symbol == '7')) {
-                                edges_crossed += 1;
-                            }
+                        if is_in_path
+                            && sample.is_some_and(|s| !(s.symbol == 'L' || s.symbol == '7'))
+                        {
+                            edges_crossed += 1;
                         }
                         x2 += 1;
                         y2 += 1;
                     }
                     if edges_crossed % 2 == 1 {
-                        row_print += "I";
                         sum += 1;
-                    } else {
-                        row_print += "O";
                     }
-                } else {
-                    row_print += &col.unwrap().symbol.to_string();
                 }
             }
-            println!("Row -> {}", row_print);
         }
-        println!("Total inside {}", sum);
+        sum
     }
 }
 
-fn read_file(filename: &str) -> usize {
-    let file_contents = read_to_string(filename).unwrap();
+fn read_file(filename: &str, start_choice: Option<usize>) -> Result<PipeGrid, String> {
+    let file_contents =
+        read_to_string(filename).map_err(|e| format!("Can't read {}: {}", filename, e))?;
     let lines: Vec<&str> = file_contents.lines().collect();
-    let mut grid = PipeGrid::from_lines(&lines);
-
-    let loop_len = grid.get_loop_len();
-    grid.count_enclosed_nones();
-    loop_len / 2
+    PipeGrid::from_lines(&lines, start_choice)
 }
 
 fn main() {
-    let line_results = read_file("input");
+    // Usage: day10 [--check] [--start <n>] [--render] [--image <file>] [--distance] [--scale <n>]
+    // --check also counts the enclosed tiles by ray casting and compares the two
+    // --start picks which loop to follow when S is ambiguous, as numbered in the listing
+    // --render draws the grid for a terminal, --image writes a .png (or .ppm for any other name)
+    // --distance colours the loop by distance from S, --scale is pixels per tile in images
+    let args: Vec<String> = env::args().skip(1).collect();
+    let flag_value = |flag: &str| {
+        args.iter()
+            .position(|a| a == flag)
+            .and_then(|i| args.get(i + 1))
+    };
+    let check = args.iter().any(|a| a == "--check");
+    let start_choice = flag_value("--start").and_then(|n| n.parse().ok());
+    let grid = match read_file("input", start_choice) {
+        Ok(grid) => grid,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
 
-    println!("Total {}", line_results);
+    let options = RenderOptions {
+        distance_colours: args.iter().any(|a| a == "--distance"),
+        scale: flag_value("--scale")
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(3),
+    };
+    if args.iter().any(|a| a == "--render") {
+        print!("{}", render::to_ansi(&grid, &options));
+    }
+    if let Some(filename) = flag_value("--image") {
+        if let Err(e) = render::write_image(&grid, &options, filename) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+
+    let enclosed = grid.count_enclosed();
+    if check {
+        let by_ray = grid.count_enclosed_nones();
+        println!("Shoelace/Pick {} ray casting {}", enclosed, by_ray);
+        assert_eq!(enclosed, by_ray);
+    }
+    println!("Total {}", grid.get_loop_len() / 2);
+    println!("Total inside {}", enclosed);
 }
@@ -0,0 +1,182 @@
+use crate::{PipeCell, PipeDirection, PipeGrid};
+use std::{fs::File, io::BufWriter};
+
+type Rgb = (u8, u8, u8);
+
+const INSIDE: Rgb = (40, 90, 50);
+const OUTSIDE: Rgb = (25, 25, 35);
+const LOOP: Rgb = (255, 200, 60);
+const JUNK: Rgb = (95, 95, 105);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    /// Part of the loop, with its distance from S going the shorter way round
+    Loop(PipeCell, usize),
+    /// A pipe that isn't on the loop
+    Junk(PipeCell, bool),
+    Ground(bool),
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderOptions {
+    /// Colour the loop by distance from S instead of one flat colour
+    pub distance_colours: bool,
+    /// Pixels per tile in images, odd so pipes run through a centre pixel
+    pub scale: usize,
+}
+
+pub fn box_char(symbol: char) -> char {
+    match symbol {
+        '|' => '│',
+        '-' => '─',
+        'L' => '└',
+        'J' => '┘',
+        '7' => '┐',
+        'F' => '┌',
+        _ => symbol,
+    }
+}
+
+/// Every tile with whether it's inside the loop, found by scanning each row and flipping
+/// at loop pipes that go north, which is the number of times a ray along the row crosses it
+fn classify(pipes: &PipeGrid) -> Vec<Vec<Tile>> {
+    let mut distance: Vec<Vec<Option<usize>>> =
+        pipes.grid.iter().map(|row| vec![None; row.len()]).collect();
+    let loop_len = pipes.path.len();
+    for (i, p) in pipes.path.iter().enumerate() {
+        distance[p.x][p.y] = Some(i.min(loop_len - i));
+    }
+    pipes
+        .grid
+        .iter()
+        .enumerate()
+        .map(|(x, row)| {
+            let mut inside = false;
+            row.iter()
+                .enumerate()
+                .map(|(y, cell)| match (cell, distance[x][y]) {
+                    (Some(cell), Some(d)) => {
+                        if cell.connects(PipeDirection::North) {
+                            inside = !inside;
+                        }
+                        Tile::Loop(*cell, d)
+                    }
+                    (Some(cell), None) => Tile::Junk(*cell, inside),
+                    (None, _) => Tile::Ground(inside),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Blue at S through to red at the far side of the loop
+fn distance_colour(distance: usize, furthest: usize) -> Rgb {
+    let t = distance as f64 / furthest.max(1) as f64;
+    ((255.0 * t) as u8, 80, (255.0 * (1.0 - t)) as u8)
+}
+
+fn loop_colour(distance: usize, furthest: usize, options: &RenderOptions) -> Rgb {
+    if options.distance_colours {
+        distance_colour(distance, furthest)
+    } else {
+        LOOP
+    }
+}
+
+fn background(inside: bool) -> Rgb {
+    if inside {
+        INSIDE
+    } else {
+        OUTSIDE
+    }
+}
+
+/// 24 bit colour escapes, one line per grid row
+pub fn to_ansi(pipes: &PipeGrid, options: &RenderOptions) -> String {
+    let furthest = pipes.path.len() / 2;
+    let mut res = String::new();
+    for row in classify(pipes) {
+        for tile in row {
+            let (fg, bg, c, dim) = match tile {
+                Tile::Loop(cell, d) => (
+                    loop_colour(d, furthest, options),
+                    OUTSIDE,
+                    box_char(cell.symbol),
+                    false,
+                ),
+                Tile::Junk(cell, inside) => (JUNK, background(inside), box_char(cell.symbol), true),
+                Tile::Ground(inside) => (JUNK, background(inside), ' ', false),
+            };
+            if dim {
+                res += "\x1b[2m";
+            }
+            res += &format!(
+                "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m{}\x1b[0m",
+                fg.0, fg.1, fg.2, bg.0, bg.1, bg.2, c
+            );
+        }
+        res += "\n";
+    }
+    res
+}
+
+/// Each tile is scale x scale pixels, with a pipe drawn from the centre out along its connections
+fn to_pixels(pipes: &PipeGrid, options: &RenderOptions) -> (usize, usize, Vec<u8>) {
+    let scale = options.scale.max(1) | 1;
+    let centre = scale / 2;
+    let furthest = pipes.path.len() / 2;
+    let tiles = classify(pipes);
+    let width = tiles.iter().map(|row| row.len()).max().unwrap_or(0) * scale;
+    let height = tiles.len() * scale;
+    let mut pixels = vec![0; width * height * 3];
+    for (x, row) in tiles.iter().enumerate() {
+        for (y, tile) in row.iter().enumerate() {
+            let (pipe, colour, bg) = match *tile {
+                Tile::Loop(cell, d) => (Some(cell), loop_colour(d, furthest, options), OUTSIDE),
+                Tile::Junk(cell, inside) => (Some(cell), JUNK, background(inside)),
+                Tile::Ground(inside) => (None, JUNK, background(inside)),
+            };
+            for i in 0..scale {
+                for j in 0..scale {
+                    let on_pipe = pipe.is_some_and(|cell| {
+                        (i == centre && j == centre)
+                            || (i == centre && j < centre && cell.connects(PipeDirection::West))
+                            || (i == centre && j > centre && cell.connects(PipeDirection::East))
+                            || (j == centre && i < centre && cell.connects(PipeDirection::North))
+                            || (j == centre && i > centre && cell.connects(PipeDirection::South))
+                    });
+                    let (r, g, b) = if on_pipe { colour } else { bg };
+                    let offset = ((x * scale + i) * width + y * scale + j) * 3;
+                    pixels[offset..offset + 3].copy_from_slice(&[r, g, b]);
+                }
+            }
+        }
+    }
+    (width, height, pixels)
+}
+
+pub fn to_ppm(pipes: &PipeGrid, options: &RenderOptions) -> Vec<u8> {
+    let (width, height, pixels) = to_pixels(pipes, options);
+    let mut res = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    res.extend(pixels);
+    res
+}
+
+/// Writes a .png, or a .ppm for anything else
+pub fn write_image(
+    pipes: &PipeGrid,
+    options: &RenderOptions,
+    filename: &str,
+) -> Result<(), String> {
+    let error = |e: &dyn std::fmt::Display| format!("Can't write {}: {}", filename, e);
+    if !filename.ends_with(".png") {
+        return std::fs::write(filename, to_ppm(pipes, options)).map_err(|e| error(&e));
+    }
+    let (width, height, pixels) = to_pixels(pipes, options);
+    let file = File::create(filename).map_err(|e| error(&e))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| error(&e))?;
+    writer.write_image_data(&pixels).map_err(|e| error(&e))
+}
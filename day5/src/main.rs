@@ -1,5 +1,11 @@
-#![feature(btree_cursors)]
-use std::{collections::BTreeMap, fs::read_to_string, ops::Bound};
+use std::{
+    collections::{HashMap, VecDeque},
+    env, fmt,
+    fs::read_to_string,
+    hint::black_box,
+    ops::RangeInclusive,
+    time::Instant,
+};
 
 #[derive(Debug, Clone)]
 struct RangeMapper {
@@ -19,33 +25,132 @@ impl RangeMapper {
         }
     }
     pub fn get_range_start(&self) -> u64 {
-        return self.range_a_start;
+        self.range_a_start
     }
-    pub fn convert_a_key_to_b_key_or_passthrough(&self, value: u64) -> u64 {
-        if value > (self.range_a_start + self.length - 1) {
-            value
+    /// One past the last source key covered, widened so it can't overflow
+    pub fn get_range_end(&self) -> u128 {
+        self.range_a_start as u128 + self.length as u128
+    }
+    pub fn overflows(&self) -> bool {
+        let max = u64::MAX as u128 + 1;
+        self.get_range_end() > max || self.range_b_start as u128 + self.length as u128 > max
+    }
+    /// None if the mapped key is past u64::MAX, which only an overflowing mapper can do
+    pub fn convert_a_key_to_b_key_or_passthrough(&self, value: u64) -> Option<u64> {
+        if value < self.range_a_start || value - self.range_a_start >= self.length {
+            Some(value)
         } else {
-            self.range_b_start + (value - self.range_a_start)
+            self.range_b_start.checked_add(value - self.range_a_start)
         }
     }
 }
-#[derive(Debug, Clone)]
+impl fmt::Display for RangeMapper {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Same order as the almanac lines
+        write!(
+            f,
+            "{} {} {}",
+            self.range_b_start, self.range_a_start, self.length
+        )
+    }
+}
+#[derive(Debug, Default)]
+struct StageReport {
+    duplicates: Vec<RangeMapper>,
+    overlaps: Vec<(RangeMapper, RangeMapper)>,
+    overflows: Vec<RangeMapper>,
+    empty: Vec<RangeMapper>,
+    gaps: Vec<RangeInclusive<u64>>,
+}
+impl StageReport {
+    pub fn is_valid(&self) -> bool {
+        self.duplicates.is_empty()
+            && self.overlaps.is_empty()
+            && self.overflows.is_empty()
+            && self.empty.is_empty()
+    }
+    pub fn print(&self) {
+        for ranger in &self.duplicates {
+            println!("  duplicate start, overwritten: {}", ranger);
+        }
+        for (a, b) in &self.overlaps {
+            println!("  overlapping sources: {} / {}", a, b);
+        }
+        for ranger in &self.overflows {
+            println!("  overflows u64: {}", ranger);
+        }
+        for ranger in &self.empty {
+            println!("  zero length: {}", ranger);
+        }
+        for gap in &self.gaps {
+            println!("  pass-through {}..={}", gap.start(), gap.end());
+        }
+    }
+}
+#[derive(Debug, Clone, Default)]
 struct RangeSet {
-    rangers: BTreeMap<u64, RangeMapper>,
+    // Kept sorted by range start so lookups can binary search
+    rangers: Vec<RangeMapper>,
+    // Mappers that were replaced by a later one with the same start, kept for validation
+    overwritten: Vec<RangeMapper>,
 }
 impl RangeSet {
     fn insert(&mut self, value: RangeMapper) {
-        self.rangers.insert(value.get_range_start(), value);
-    }
-    pub fn lookup(&self, value: u64) -> u64 {
-        //Lookup
-        let base_element = self.rangers.upper_bound(Bound::Included(&value));
-        match base_element.key() {
-            Some(matched_key) => {
-                let ranger = &self.rangers[matched_key];
-                ranger.convert_a_key_to_b_key_or_passthrough(value)
+        match self
+            .rangers
+            .binary_search_by_key(&value.get_range_start(), |r| r.get_range_start())
+        {
+            Ok(index) => {
+                let old = std::mem::replace(&mut self.rangers[index], value);
+                self.overwritten.push(old);
+            }
+            Err(index) => self.rangers.insert(index, value),
+        }
+    }
+    /// Check the mappers for problems, and list the source ranges that pass straight through
+    pub fn validate(&self) -> StageReport {
+        let mut report = StageReport {
+            duplicates: self.overwritten.clone(),
+            ..Default::default()
+        };
+        let mut cursor: u128 = 0; // First source key not yet covered by a mapper
+        let mut cursor_owner: Option<&RangeMapper> = None; // The mapper that reaches out to cursor
+        for ranger in &self.rangers {
+            if ranger.length == 0 {
+                report.empty.push(ranger.clone());
+                continue;
             }
-            None => value, // Yield value through if no match
+            if ranger.overflows() {
+                report.overflows.push(ranger.clone());
+            }
+            let start = ranger.get_range_start() as u128;
+            // Compare against the furthest reach so far, not just the previous mapper,
+            // so a long mapper still catches overlaps past the ones it already covers
+            if let Some(owner) = cursor_owner.filter(|_| start < cursor) {
+                report.overlaps.push((owner.clone(), ranger.clone()));
+            }
+            if start > cursor {
+                report.gaps.push(cursor as u64..=(start - 1) as u64);
+            }
+            if ranger.get_range_end() > cursor {
+                cursor = ranger.get_range_end();
+                cursor_owner = Some(ranger);
+            }
+        }
+        if cursor <= u64::MAX as u128 {
+            report.gaps.push(cursor as u64..=u64::MAX);
+        }
+        report
+    }
+    pub fn lookup(&self, value: u64) -> Option<u64> {
+        //Lookup the last ranger starting at or below the value
+        let index = self
+            .rangers
+            .partition_point(|r| r.get_range_start() <= value);
+        if index == 0 {
+            Some(value) // Yield value through if no match
+        } else {
+            self.rangers[index - 1].convert_a_key_to_b_key_or_passthrough(value)
         }
     }
 
@@ -54,73 +159,269 @@ impl RangeSet {
         self.insert(RangeMapper::from_str(line))
     }
 }
-impl Default for RangeSet {
-    fn default() -> Self {
+#[derive(Debug, Clone)]
+struct CategoryMap {
+    source: String,
+    target: String,
+    ranges: RangeSet,
+}
+impl CategoryMap {
+    pub fn from_header(header: &str) -> Self {
+        // Header is of the form "seed-to-soil map:"
+        let name = header.trim().trim_end_matches(':').trim_end_matches(" map");
+        let (source, target) = name
+            .split_once("-to-")
+            .unwrap_or_else(|| panic!("Bad map header {}", header));
         Self {
-            rangers: Default::default(),
+            source: source.to_owned(),
+            target: target.to_owned(),
+            ranges: RangeSet::default(),
         }
     }
 }
+#[derive(Debug, Clone, Default)]
+struct Almanac {
+    seeds: Vec<u64>,
+    maps: Vec<CategoryMap>,
+}
+impl Almanac {
+    pub fn from_str(input: &str) -> Self {
+        let mut almanac = Self::default();
+        for line in input.lines() {
+            let cline = line.trim();
+            if cline.is_empty() {
+                continue;
+            }
+            if let Some(seeds) = cline.strip_prefix("seeds:") {
+                almanac.seeds = string_to_split_nums(seeds);
+            } else if cline.chars().next().unwrap().is_ascii_digit() {
+                //Digit line, ingest into the most recent map
+                almanac
+                    .maps
+                    .last_mut()
+                    .expect("Range line before any map header")
+                    .ranges
+                    .insert_line(cline);
+            } else {
+                almanac.maps.push(CategoryMap::from_header(cline));
+            }
+        }
+        almanac
+    }
+    pub fn get_seeds(&self) -> &[u64] {
+        &self.seeds
+    }
+    /// Validate every stage, printing the findings; returns true if no stage had problems
+    pub fn validate(&self) -> bool {
+        let mut all_valid = true;
+        for map in &self.maps {
+            let report = map.ranges.validate();
+            println!(
+                "{}-to-{}: {}",
+                map.source,
+                map.target,
+                if report.is_valid() { "ok" } else { "INVALID" }
+            );
+            report.print();
+            all_valid &= report.is_valid();
+        }
+        all_valid
+    }
+    /// Find the chain of maps that converts `source` category numbers into `target` category numbers
+    /// Maps can be listed in any order, and categories can branch; the shortest chain is returned
+    pub fn find_path(&self, source: &str, target: &str) -> Option<Vec<&RangeSet>> {
+        let mut came_from: HashMap<&str, usize> = HashMap::new();
+        let mut queue: VecDeque<&str> = VecDeque::new();
+        queue.push_back(source);
+        while let Some(category) = queue.pop_front() {
+            if category == target {
+                // Walk back along the maps we used to get here
+                let mut path = Vec::new();
+                let mut cur = category;
+                while cur != source {
+                    let map = &self.maps[came_from[cur]];
+                    path.push(&map.ranges);
+                    cur = &map.source;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for (index, map) in self.maps.iter().enumerate() {
+                if map.source == category
+                    && map.target != source
+                    && !came_from.contains_key(map.target.as_str())
+                {
+                    came_from.insert(&map.target, index);
+                    queue.push_back(&map.target);
+                }
+            }
+        }
+        None
+    }
+}
 fn string_to_split_nums(chunk_in: &str) -> Vec<u64> {
     let mut res = Vec::new();
     for s in chunk_in.split(' ') {
         let safe = s.trim();
-        if safe.len() > 0 {
+        if !safe.is_empty() {
             res.push(s.parse().unwrap());
         }
     }
     res
 }
-fn find_lowest_location(rangers: &[RangeSet], seeds: &[u64]) -> u64 {
-    let mut lowest_location = 0xFFFFFFFF;
-    for seed_pair in seeds.chunks(2) {
-        for seed in seed_pair[0]..seed_pair[0] + seed_pair[1] {
-            let mut current_value = seed;
-            for stage in rangers {
-                current_value = stage.lookup(current_value);
-            }
-            if current_value < lowest_location {
-                lowest_location = current_value;
+/// None if any stage maps the value past u64::MAX
+fn run_through_stages(rangers: &[&RangeSet], value: u64) -> Option<u64> {
+    let mut current_value = value;
+    for stage in rangers {
+        current_value = stage.lookup(current_value)?;
+    }
+    Some(current_value)
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SeedMode {
+    /// Part 1, every number on the seeds line is a seed
+    Individual,
+    /// Part 2, the seeds line is (start, length) pairs
+    Ranges,
+}
+impl SeedMode {
+    /// Inclusive so a seed or range ending at u64::MAX still fits
+    pub fn seed_ranges(&self, seeds: &[u64]) -> Result<Vec<RangeInclusive<u64>>, String> {
+        match self {
+            SeedMode::Individual => Ok(seeds.iter().map(|s| *s..=*s).collect()),
+            SeedMode::Ranges => {
+                if !seeds.len().is_multiple_of(2) {
+                    return Err(format!(
+                        "Seed {} has no length to pair with",
+                        seeds.last().unwrap()
+                    ));
+                }
+                seeds
+                    .chunks(2)
+                    .filter(|pair| pair[1] > 0)
+                    .map(|pair| match pair[0].checked_add(pair[1] - 1) {
+                        Some(end) => Ok(pair[0]..=end),
+                        None => Err(format!(
+                            "Seed range {} {} runs past u64::MAX",
+                            pair[0], pair[1]
+                        )),
+                    })
+                    .collect()
             }
         }
     }
-    lowest_location
 }
-fn read_file(filename: &str) -> u64 {
-    let mut rangers: Vec<RangeSet> = Vec::new();
-    let mut seeds = Vec::new();
-    let mut current_range_set = RangeSet::default();
-
-    for line in read_to_string(filename).unwrap().lines() {
-        if seeds.len() == 0 {
-            // If seeds is empty we are on first line, split it and yeet them into the vector
-            let parts: Vec<&str> = line.split(':').collect();
-            seeds = string_to_split_nums(parts[1]);
-        } else {
-            //We are in the following sections
-            //If line is blank, break the current ranger and start a new one
-            let cline = line.trim();
-            if cline.len() == 0 {
-                //Line break
-                rangers.push(current_range_set);
-                current_range_set = RangeSet::default();
-            } else if cline.chars().next().unwrap().is_ascii_digit() {
-                //Digit line, ingest into the map
-                current_range_set.insert_line(cline);
+/// Returns the lowest location and the seed that produced it
+fn find_lowest_location(
+    rangers: &[&RangeSet],
+    seeds: &[u64],
+    mode: SeedMode,
+) -> Result<Option<(u64, u64)>, String> {
+    let mut lowest: Option<(u64, u64)> = None;
+    for seed_range in mode.seed_ranges(seeds)? {
+        for seed in seed_range {
+            // Seeds that overflow a stage can't give a real location
+            let Some(current_value) = run_through_stages(rangers, seed) else {
+                continue;
+            };
+            if lowest.is_none_or(|(location, _)| current_value < location) {
+                lowest = Some((current_value, seed));
             }
         }
     }
-    rangers.push(current_range_set);
-
-    // We now have all of our rangers; we now need to lookup each seed through the ranges
-    //For part 2, these are run-length encoded
-    find_lowest_location(&rangers, &seeds)
+    Ok(lowest)
+}
+/// Time `RangeSet::lookup` across every stage of the chain with pseudo-random inputs
+fn bench_lookups(rangers: &[&RangeSet], iterations: usize) {
+    // Fixed xorshift seed so runs are comparable
+    let mut state: u64 = 0x2545F4914F6CDD1D;
+    let values: Vec<u64> = (0..iterations)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state & 0xFFFFFFFF
+        })
+        .collect();
+    let start = Instant::now();
+    let mut checksum = 0u64;
+    for value in &values {
+        for stage in rangers {
+            checksum = checksum.wrapping_add(black_box(stage).lookup(*value).unwrap_or(0));
+        }
+    }
+    let elapsed = start.elapsed();
+    let lookups = iterations * rangers.len();
+    println!(
+        "{} lookups in {:?} -> {:.1} M lookups/s (checksum {})",
+        lookups,
+        elapsed,
+        lookups as f64 / elapsed.as_secs_f64() / 1_000_000.0,
+        checksum
+    );
+}
+fn read_file(filename: &str) -> Almanac {
+    Almanac::from_str(&read_to_string(filename).unwrap())
 }
 
 fn main() {
-    let line_results = read_file("input");
+    // Usage: day5 [source] [target] [values...]
+    //        day5 --bench [iterations]
+    //        day5 --validate
+    // With no values and a seed source, the seed ranges are run through the chain and the lowest result reported
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().is_some_and(|a| a == "--bench") {
+        let almanac = read_file("input");
+        let path = almanac.find_path("seed", "location").unwrap();
+        let iterations = args
+            .get(1)
+            .map(|i| i.parse().unwrap())
+            .unwrap_or(10_000_000);
+        bench_lookups(&path, iterations);
+        return;
+    }
+    if args.first().is_some_and(|a| a == "--validate") {
+        let valid = read_file("input").validate();
+        println!("Almanac {}", if valid { "valid" } else { "has errors" });
+        return;
+    }
+    let source = args.first().map(|s| s.as_str()).unwrap_or("seed");
+    let target = args.get(1).map(|s| s.as_str()).unwrap_or("location");
+    let almanac = read_file("input");
 
-    println!("Total {}", line_results);
+    let Some(path) = almanac.find_path(source, target) else {
+        println!("No chain of maps from {} to {}", source, target);
+        return;
+    };
+    if args.len() > 2 {
+        for value in &args[2..] {
+            let value: u64 = value.parse().unwrap();
+            match run_through_stages(&path, value) {
+                Some(result) => println!("{} {} -> {} {}", source, value, target, result),
+                None => println!("{} {} -> overflows u64", source, value),
+            }
+        }
+        return;
+    }
+    if source != "seed" {
+        // The seeds line only holds seed numbers, other categories need values to convert
+        println!("Give some {} values to convert to {}", source, target);
+        return;
+    }
+    // We now have the chain of rangers; we now need to lookup each seed through the ranges
+    //For part 2, these are run-length encoded
+    for mode in [SeedMode::Individual, SeedMode::Ranges] {
+        match find_lowest_location(&path, almanac.get_seeds(), mode) {
+            Ok(Some((location, seed))) => {
+                println!(
+                    "{:?}: lowest {} {} from seed {}",
+                    mode, target, location, seed
+                )
+            }
+            Ok(None) => println!("{:?}: no seeds", mode),
+            Err(e) => println!("{:?}: {}", mode, e),
+        }
+    }
 }
 
 //Part B -> 81956384
@@ -0,0 +1,188 @@
+use std::ops::RangeInclusive;
+
+/// What counts as beating the record distance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordRule {
+    /// Travel further than the record
+    Exceed,
+    /// Stop on the record distance exactly
+    Exact,
+}
+
+/// How far a boat travels for a given button hold
+///
+/// The solver assumes distance rises (or holds) then falls as hold time increases,
+/// so that any "at least this far" set of holds is one contiguous window
+pub trait BoatModel {
+    /// Distances too big for u128 saturate at u128::MAX, which still beats any record
+    fn distance(&self, hold_time: u128, race_time: u128) -> u128;
+    /// Holds travelling at least `min_distance`, or None if this model has no closed form
+    fn closed_form_window(
+        &self,
+        _race_time: u128,
+        _min_distance: u128,
+    ) -> Option<Option<RangeInclusive<u128>>> {
+        None
+    }
+    /// Hold that travels furthest, or None if this model has no closed form
+    fn closed_form_best_hold(&self, _race_time: u128) -> Option<u128> {
+        None
+    }
+    fn record_rule(&self) -> RecordRule {
+        RecordRule::Exceed
+    }
+}
+
+/// Speed goes up by `acceleration` for every ms held, then stays constant
+#[derive(Debug, Clone, Copy)]
+pub struct LinearBoat {
+    pub acceleration: u128,
+}
+impl Default for LinearBoat {
+    fn default() -> Self {
+        Self { acceleration: 1 }
+    }
+}
+impl BoatModel for LinearBoat {
+    fn distance(&self, hold_time: u128, race_time: u128) -> u128 {
+        let speed = hold_time.saturating_mul(self.acceleration);
+        let time_left = race_time - hold_time;
+        time_left.saturating_mul(speed)
+    }
+    /// hold * (time - hold) peaks half way, and saturating distances can't flatten that out
+    fn closed_form_best_hold(&self, race_time: u128) -> Option<u128> {
+        Some(race_time / 2)
+    }
+    /// acceleration * hold * (time - hold) >= min_distance
+    /// is hold * (time - hold) >= ceil(min_distance / acceleration), which has roots
+    /// (time +- sqrt(time^2 - 4*needed)) / 2
+    fn closed_form_window(
+        &self,
+        race_time: u128,
+        min_distance: u128,
+    ) -> Option<Option<RangeInclusive<u128>>> {
+        if min_distance == 0 {
+            return Some(Some(0..=race_time));
+        }
+        if self.acceleration == 0 {
+            return Some(None);
+        }
+        let needed = min_distance.div_ceil(self.acceleration);
+        // A product too big for u128 is certainly past `needed`
+        let reaches = |hold: u128| {
+            hold.checked_mul(race_time - hold)
+                .is_none_or(|travelled| travelled >= needed)
+        };
+        let Some(time_squared) = race_time.checked_mul(race_time) else {
+            // Too long to square, so binary search the rising half for the first reaching hold
+            // The curve is symmetric, so the window ends the same distance from the other side
+            let (mut low, mut high) = (0, race_time / 2);
+            if !reaches(high) {
+                return Some(None);
+            }
+            while low < high {
+                let mid = low + (high - low) / 2;
+                if reaches(mid) {
+                    high = mid;
+                } else {
+                    low = mid + 1;
+                }
+            }
+            return Some(Some(low..=race_time - low));
+        };
+        let Some(discriminant) = time_squared.checked_sub(needed.saturating_mul(4)) else {
+            return Some(None);
+        };
+        let root = discriminant.isqrt();
+        // The integer root can be under the real one, so these are only close to the bounds
+        // Walk each side to the exact first reaching hold
+        let mut low = (race_time - root.min(race_time)) / 2;
+        let mut high = (race_time + root).div_ceil(2).min(race_time);
+        while low <= high && !reaches(low) {
+            low += 1;
+        }
+        while high > low && !reaches(high) {
+            high -= 1;
+        }
+        if low > high || !reaches(low) {
+            return Some(None);
+        }
+        // Widen in case the estimate started inside the window
+        while low > 0 && reaches(low - 1) {
+            low -= 1;
+        }
+        while high < race_time && reaches(high + 1) {
+            high += 1;
+        }
+        Some(Some(low..=high))
+    }
+}
+
+/// Linear acceleration, but the boat can't go faster than `top_speed`
+#[derive(Debug, Clone, Copy)]
+pub struct CappedBoat {
+    pub acceleration: u128,
+    pub top_speed: u128,
+}
+impl BoatModel for CappedBoat {
+    fn distance(&self, hold_time: u128, race_time: u128) -> u128 {
+        let speed = hold_time
+            .saturating_mul(self.acceleration)
+            .min(self.top_speed);
+        (race_time - hold_time).saturating_mul(speed)
+    }
+}
+
+/// Linear acceleration, then the boat loses `drag` speed every ms until it stops
+#[derive(Debug, Clone, Copy)]
+pub struct DragBoat {
+    pub acceleration: u128,
+    pub drag: u128,
+}
+impl BoatModel for DragBoat {
+    fn distance(&self, hold_time: u128, race_time: u128) -> u128 {
+        let speed = hold_time.saturating_mul(self.acceleration);
+        let time_left = race_time - hold_time;
+        if self.drag == 0 {
+            return time_left.saturating_mul(speed);
+        }
+        // Moving for this many ms, covering speed, speed - drag, speed - 2*drag ...
+        let moving = speed.div_ceil(self.drag).min(time_left);
+        if moving == 0 {
+            return 0;
+        }
+        // Summed up from the slowest ms, so every term is positive and saturating is exact
+        // drag * (moving - 1) < speed, so the slowest speed can't underflow
+        let slowest = speed - self.drag * (moving - 1);
+        let steps = if moving.is_multiple_of(2) {
+            (moving / 2).saturating_mul(moving - 1)
+        } else {
+            moving.saturating_mul((moving - 1) / 2)
+        };
+        moving
+            .saturating_mul(slowest)
+            .saturating_add(self.drag.saturating_mul(steps))
+    }
+}
+
+/// Wraps another model so only landing on the record exactly wins
+#[derive(Debug, Clone, Copy)]
+pub struct ExactDistance<M: BoatModel>(pub M);
+impl<M: BoatModel> BoatModel for ExactDistance<M> {
+    fn distance(&self, hold_time: u128, race_time: u128) -> u128 {
+        self.0.distance(hold_time, race_time)
+    }
+    fn closed_form_window(
+        &self,
+        race_time: u128,
+        min_distance: u128,
+    ) -> Option<Option<RangeInclusive<u128>>> {
+        self.0.closed_form_window(race_time, min_distance)
+    }
+    fn closed_form_best_hold(&self, race_time: u128) -> Option<u128> {
+        self.0.closed_form_best_hold(race_time)
+    }
+    fn record_rule(&self) -> RecordRule {
+        RecordRule::Exact
+    }
+}
@@ -1,63 +1,291 @@
-use std::fs::read_to_string;
+mod boat;
+mod report;
+
+use boat::{BoatModel, CappedBoat, DragBoat, ExactDistance, LinearBoat, RecordRule};
+use report::RaceReport;
+use std::{env, fs::read_to_string, ops::RangeInclusive};
 
 #[derive(Debug, Clone)]
-struct TimedRace {
-    time: u64,
-    distance: u64,
+struct TimedRace<M: BoatModel = LinearBoat> {
+    time: u128,
+    distance: u128,
+    model: M,
 }
 impl TimedRace {
-    pub fn make(time: u64, distance: u64) -> Self {
-        Self { time, distance }
+    pub fn make(time: u128, distance: u128) -> Self {
+        Self::with_model(time, distance, LinearBoat::default())
+    }
+}
+impl<M: BoatModel> TimedRace<M> {
+    pub fn with_model(time: u128, distance: u128, model: M) -> Self {
+        Self {
+            time,
+            distance,
+            model,
+        }
+    }
+    pub fn distance_for_hold(&self, hold_time: u128) -> u128 {
+        self.model.distance(hold_time, self.time)
+    }
+    fn is_winning_hold(&self, hold_time: u128) -> bool {
+        let travelled = self.distance_for_hold(hold_time);
+        match self.model.record_rule() {
+            RecordRule::Exceed => travelled > self.distance,
+            RecordRule::Exact => travelled == self.distance,
+        }
     }
-    pub fn get_winning_button_times(&self) -> Vec<u64> {
+    /// Brute force every hold time, only sensible for small races
+    pub fn get_winning_button_times(&self) -> Vec<u128> {
         let mut res = Vec::new();
-        for hold_time in 1..self.time - 1 {
-            let speed = hold_time;
-            let time_left = self.time - hold_time;
-            let distance = time_left * speed;
-            if distance > self.distance {
+        for hold_time in 0..=self.time {
+            if self.is_winning_hold(hold_time) {
                 res.push(hold_time);
             }
         }
         res
     }
+    /// Hold that travels furthest, found by ternary search over the rise-then-fall curve
+    /// unless the model knows it directly
+    pub fn get_best_hold(&self) -> u128 {
+        if let Some(best) = self.model.closed_form_best_hold(self.time) {
+            return best;
+        }
+        let (mut low, mut high) = (0, self.time);
+        while high - low > 2 {
+            let third = (high - low) / 3;
+            if self.distance_for_hold(low + third) < self.distance_for_hold(high - third) {
+                low += third + 1;
+            } else {
+                high -= third;
+            }
+        }
+        (low..=high)
+            .max_by_key(|h| self.distance_for_hold(*h))
+            .unwrap()
+    }
+    /// All holds travelling at least `min_distance`, closed form if the model has one
+    pub fn get_hold_window_reaching(&self, min_distance: u128) -> Option<RangeInclusive<u128>> {
+        if let Some(window) = self.model.closed_form_window(self.time, min_distance) {
+            return window;
+        }
+        let reaches = |hold: u128| self.distance_for_hold(hold) >= min_distance;
+        let best = self.get_best_hold();
+        if !reaches(best) {
+            return None;
+        }
+        // Binary search each side of the peak for the edge of the window
+        let (mut low, mut high) = (0, best);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if reaches(mid) {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        let first = low;
+        let (mut low, mut high) = (best, self.time);
+        while low < high {
+            let mid = low + (high - low).div_ceil(2);
+            if reaches(mid) {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        Some(first..=low)
+    }
+    /// Windows of winning holds, one for beating the record, up to two for hitting it exactly
+    pub fn get_winning_hold_windows(&self) -> Vec<RangeInclusive<u128>> {
+        // Nothing can beat a record of u128::MAX
+        let beating = self
+            .distance
+            .checked_add(1)
+            .and_then(|record| self.get_hold_window_reaching(record));
+        match self.model.record_rule() {
+            RecordRule::Exceed => beating.into_iter().collect(),
+            RecordRule::Exact => {
+                let Some(reaching) = self.get_hold_window_reaching(self.distance) else {
+                    return Vec::new();
+                };
+                let Some(beating) = beating else {
+                    return vec![reaching];
+                };
+                let mut res = Vec::new();
+                if beating.start() > reaching.start() {
+                    res.push(*reaching.start()..=beating.start() - 1);
+                }
+                if beating.end() < reaching.end() {
+                    res.push(beating.end() + 1..=*reaching.end());
+                }
+                res
+            }
+        }
+    }
+    pub fn count_winning_holds(&self) -> u128 {
+        self.get_winning_hold_windows()
+            .iter()
+            .map(|window| window.end() - window.start() + 1)
+            .sum()
+    }
 }
 
-fn string_to_split_nums(chunk_in: &str) -> Vec<u64> {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RaceMode {
+    /// Part 1, each column is its own race
+    Separate,
+    /// Part 2, the spaces are bad kerning and each line is one big number
+    Kerned,
+}
+impl RaceMode {
+    pub fn parse_line(&self, line: &str) -> Vec<u128> {
+        let values = line.split(':').collect::<Vec<&str>>()[1];
+        match self {
+            RaceMode::Separate => string_to_split_nums(values),
+            RaceMode::Kerned => {
+                let digits: String = values.chars().filter(|c| !c.is_whitespace()).collect();
+                vec![digits.parse().unwrap()]
+            }
+        }
+    }
+}
+
+fn string_to_split_nums(chunk_in: &str) -> Vec<u128> {
     let mut res = Vec::new();
     for s in chunk_in.split(' ') {
         let safe = s.trim();
-        if safe.len() > 0 {
+        if !safe.is_empty() {
             res.push(s.parse().unwrap());
         }
     }
     res
 }
 
-fn read_file(filename: &str) -> u64 {
-    let mut races: Vec<TimedRace> = Vec::new();
-    {
-        let file_contents = read_to_string(filename).unwrap();
-        let lines: Vec<&str> = file_contents.lines().collect();
-        let times = string_to_split_nums(lines[0].split(':').collect::<Vec<&str>>()[1]);
-        let distances = string_to_split_nums(lines[1].split(':').collect::<Vec<&str>>()[1]);
-        for (time, dist) in times.iter().zip(distances.iter()) {
-            let race = TimedRace::make(*time, *dist);
-            races.push(race);
+/// Group sorted hold times into runs of consecutive holds
+fn holds_to_windows(holds: &[u128]) -> Vec<RangeInclusive<u128>> {
+    let mut res: Vec<RangeInclusive<u128>> = Vec::new();
+    for hold in holds {
+        match res.last_mut() {
+            Some(window) if window.end() + 1 == *hold => *window = *window.start()..=*hold,
+            _ => res.push(*hold..=*hold),
+        }
+    }
+    res
+}
+
+/// Compare the solver against brute force for every small race
+fn cross_check_model<M: BoatModel + Copy + std::fmt::Debug>(model: M, max_time: u128) -> usize {
+    let mut checked = 0;
+    for time in 0..=max_time {
+        let furthest = (0..=time).map(|h| model.distance(h, time)).max().unwrap();
+        for distance in 0..=furthest + 1 {
+            let race = TimedRace::with_model(time, distance, model);
+            let brute = race.get_winning_button_times();
+            assert_eq!(
+                race.get_winning_hold_windows(),
+                holds_to_windows(&brute),
+                "Mismatch for {:?}",
+                race
+            );
+            assert_eq!(race.count_winning_holds(), brute.len() as u128);
+            checked += 1;
         }
     }
+    checked
+}
+
+fn cross_check_small_races(max_time: u128) {
+    let mut checked = 0;
+    for acceleration in 0..4 {
+        checked += cross_check_model(LinearBoat { acceleration }, max_time);
+        checked += cross_check_model(ExactDistance(LinearBoat { acceleration }), max_time);
+        for top_speed in [0, 1, 5, 20] {
+            let capped = CappedBoat {
+                acceleration,
+                top_speed,
+            };
+            checked += cross_check_model(capped, max_time);
+            checked += cross_check_model(ExactDistance(capped), max_time);
+        }
+        for drag in 0..4 {
+            let dragged = DragBoat { acceleration, drag };
+            checked += cross_check_model(dragged, max_time);
+            checked += cross_check_model(ExactDistance(dragged), max_time);
+        }
+    }
+    println!("Solver matches brute force for {} races", checked);
+}
+
+fn read_races(filename: &str, mode: RaceMode) -> Vec<TimedRace> {
+    let mut races: Vec<TimedRace> = Vec::new();
+    let file_contents = read_to_string(filename).unwrap();
+    let lines: Vec<&str> = file_contents.lines().collect();
+    let times = mode.parse_line(lines[0]);
+    let distances = mode.parse_line(lines[1]);
+    for (time, dist) in times.iter().zip(distances.iter()) {
+        let race = TimedRace::make(*time, *dist);
+        races.push(race);
+    }
+    races
+}
+
+fn read_file(filename: &str, mode: RaceMode) -> u128 {
+    let races = read_races(filename, mode);
     //Have the races, now need to solve potentials
     let mut possible_win_combos = 1;
     for race in races {
-        let winning_holds = race.get_winning_button_times();
-        // println!("Race Winners {:?}", winning_holds);
-        possible_win_combos *= winning_holds.len();
+        match race.get_winning_hold_windows().first() {
+            Some(window) => println!(
+                "Race {} / {} -> holds {}..={} ({} ways)",
+                race.time,
+                race.distance,
+                window.start(),
+                window.end(),
+                race.count_winning_holds()
+            ),
+            None => println!("Race {} / {} -> unwinnable", race.time, race.distance),
+        }
+        possible_win_combos *= race.count_winning_holds();
     }
-    possible_win_combos as u64
+    possible_win_combos
 }
 
 fn main() {
-    let line_results = read_file("input");
+    // Usage: day6 [--check | --report | --json]
+    let flag = env::args().nth(1);
+    match flag.as_deref() {
+        Some("--check") => {
+            cross_check_small_races(60);
+            return;
+        }
+        Some("--report") => {
+            for mode in [RaceMode::Separate, RaceMode::Kerned] {
+                let reports: Vec<RaceReport> = read_races("input", mode)
+                    .iter()
+                    .map(RaceReport::from_race)
+                    .collect();
+                println!("{:?}", mode);
+                report::print_table(&reports);
+            }
+            return;
+        }
+        Some("--json") => {
+            let mut modes = Vec::new();
+            for mode in [RaceMode::Separate, RaceMode::Kerned] {
+                let reports: Vec<RaceReport> = read_races("input", mode)
+                    .iter()
+                    .map(RaceReport::from_race)
+                    .collect();
+                modes.push(format!("\"{:?}\":{}", mode, report::to_json(&reports)));
+            }
+            println!("{{{}}}", modes.join(","));
+            return;
+        }
+        _ => {}
+    }
+    for mode in [RaceMode::Separate, RaceMode::Kerned] {
+        let line_results = read_file("input", mode);
 
-    println!("Total {}", line_results);
+        println!("{:?} Total {}", mode, line_results);
+    }
 }
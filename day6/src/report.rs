@@ -0,0 +1,118 @@
+use crate::{
+    boat::{BoatModel, RecordRule},
+    TimedRace,
+};
+
+/// Strategy summary for a single race
+#[derive(Debug, Clone)]
+pub struct RaceReport {
+    pub time: u128,
+    pub record: u128,
+    pub best_hold: u128,
+    /// None when the distance doesn't fit in a u128
+    pub max_distance: Option<u128>,
+    pub winning_holds: u128,
+    /// How many ms shorter than the best hold still wins
+    pub margin_below: Option<u128>,
+    /// How many ms longer than the best hold still wins
+    pub margin_above: Option<u128>,
+    /// How far the record can rise before the race becomes unbeatable
+    pub headroom: Option<u128>,
+}
+impl RaceReport {
+    pub fn from_race<M: BoatModel>(race: &TimedRace<M>) -> Self {
+        let best_hold = race.get_best_hold();
+        // Models saturate rather than overflow, so the top value means "too far to count"
+        let max_distance = Some(race.distance_for_hold(best_hold)).filter(|d| *d != u128::MAX);
+        let windows = race.get_winning_hold_windows();
+        let first_win = windows.first().map(|w| *w.start());
+        let last_win = windows.last().map(|w| *w.end());
+        let headroom = match race.model.record_rule() {
+            RecordRule::Exceed => race
+                .distance
+                .checked_add(1)
+                .and_then(|beaten| max_distance?.checked_sub(beaten)),
+            RecordRule::Exact => max_distance.and_then(|d| d.checked_sub(race.distance)),
+        };
+        Self {
+            time: race.time,
+            record: race.distance,
+            best_hold,
+            max_distance,
+            winning_holds: race.count_winning_holds(),
+            margin_below: first_win.map(|first| best_hold.saturating_sub(first)),
+            margin_above: last_win.map(|last| last.saturating_sub(best_hold)),
+            headroom,
+        }
+    }
+    /// Record as a percentage of the furthest possible distance
+    pub fn record_percent(&self) -> f64 {
+        match self.max_distance {
+            Some(0) => 100.0,
+            Some(max_distance) => self.record as f64 / max_distance as f64 * 100.0,
+            None => 0.0,
+        }
+    }
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"time\":{},\"record\":{},\"best_hold\":{},\"max_distance\":{},\"winning_holds\":{},\"margin_below\":{},\"margin_above\":{},\"headroom\":{},\"record_percent\":{:.4}}}",
+            self.time,
+            self.record,
+            self.best_hold,
+            json_optional(self.max_distance),
+            self.winning_holds,
+            json_optional(self.margin_below),
+            json_optional(self.margin_above),
+            json_optional(self.headroom),
+            self.record_percent()
+        )
+    }
+}
+
+fn json_optional(value: Option<u128>) -> String {
+    match value {
+        Some(v) => v.to_string(),
+        None => "null".to_owned(),
+    }
+}
+
+fn table_optional(value: Option<u128>) -> String {
+    match value {
+        Some(v) => v.to_string(),
+        None => "-".to_owned(),
+    }
+}
+
+pub fn print_table(reports: &[RaceReport]) {
+    println!(
+        "{:>16} {:>20} {:>16} {:>20} {:>16} {:>12} {:>12} {:>20} {:>9}",
+        "time",
+        "record",
+        "best hold",
+        "max distance",
+        "ways",
+        "margin -",
+        "margin +",
+        "headroom",
+        "record %"
+    );
+    for report in reports {
+        println!(
+            "{:>16} {:>20} {:>16} {:>20} {:>16} {:>12} {:>12} {:>20} {:>9.3}",
+            report.time,
+            report.record,
+            report.best_hold,
+            table_optional(report.max_distance),
+            report.winning_holds,
+            table_optional(report.margin_below),
+            table_optional(report.margin_above),
+            table_optional(report.headroom),
+            report.record_percent()
+        );
+    }
+}
+
+pub fn to_json(reports: &[RaceReport]) -> String {
+    let races: Vec<String> = reports.iter().map(|r| r.to_json()).collect();
+    format!("[{}]", races.join(","))
+}
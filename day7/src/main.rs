@@ -1,46 +1,149 @@
-use std::{cmp::Ordering, collections::HashSet, fs::read_to_string};
+mod poker;
+mod stats;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+use poker::PokerHand;
+use std::{collections::HashMap, env, fs::read_to_string};
 
-enum CardType {
-    FiveOfAKind,
-    FourOfAKind,
-    FullHouse,
-    ThreeOfAKind,
-    TwoPair,
-    OnePair,
-    HighCard,
+/// A hand's type is the size of each group of matching cards, largest group first
+/// So a full house is [3, 2] and two pair is [2, 2, 1]
+/// Comparing the groups in order ranks n-of-a-kind hands of any size
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct CardType {
+    groups: Vec<usize>,
 }
 impl CardType {
-    pub fn get_rank(&self) -> usize {
-        match self {
-            CardType::FiveOfAKind => 7,
-            CardType::FourOfAKind => 6,
-            CardType::FullHouse => 5,
-            CardType::ThreeOfAKind => 4,
-            CardType::TwoPair => 3,
-            CardType::OnePair => 2,
-            CardType::HighCard => 1,
+    pub fn from_cards(cards: &[char]) -> Self {
+        let mut counts: HashMap<char, usize> = HashMap::new();
+        for card in cards {
+            *counts.entry(*card).or_default() += 1;
+        }
+        let mut groups: Vec<usize> = counts.into_values().collect();
+        groups.sort_unstable_by(|a, b| b.cmp(a));
+        Self { groups }
+    }
+    pub fn get_name(&self) -> String {
+        match self.groups.as_slice() {
+            [5] => "Five of a kind".to_owned(),
+            [4, 1] => "Four of a kind".to_owned(),
+            [3, 2] => "Full house".to_owned(),
+            [3, 1, 1] => "Three of a kind".to_owned(),
+            [2, 2, 1] => "Two pair".to_owned(),
+            [2, 1, 1, 1] => "One pair".to_owned(),
+            [1, 1, 1, 1, 1] => "High card".to_owned(),
+            groups => {
+                let sets: Vec<String> = groups
+                    .iter()
+                    .filter(|g| **g > 1)
+                    .map(|g| format!("{} of a kind", g))
+                    .collect();
+                if sets.is_empty() {
+                    "High card".to_owned()
+                } else {
+                    sets.join(" + ")
+                }
+            }
         }
     }
 }
 
-impl Ord for CardType {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.get_rank().cmp(&other.get_rank())
-    }
+/// Which set of hand types a rule set ranks by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ladder {
+    /// Unsuited cards, typed by n-of-a-kind groups, ties broken by the cards as dealt
+    CamelCards,
+    /// Suited cards with straights and flushes, ties broken by kickers
+    Poker,
 }
-impl PartialOrd for CardType {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+
+/// Card order, wildcards and hand size for a game of Camel Cards, or poker
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RuleSet {
+    name: String,
+    ladder: Ladder,
+    /// Every card in the deck, weakest first
+    ranks: Vec<char>,
+    /// Cards that become whatever makes the strongest type
+    wildcards: Vec<char>,
+    hand_size: usize,
+}
+impl RuleSet {
+    pub fn make(
+        name: &str,
+        ranks: &str,
+        wildcards: &str,
+        hand_size: usize,
+    ) -> Result<Self, String> {
+        let rules = Self {
+            name: name.to_owned(),
+            ladder: Ladder::CamelCards,
+            ranks: ranks.chars().collect(),
+            wildcards: wildcards.chars().collect(),
+            hand_size,
+        };
+        if rules.ranks.is_empty() || hand_size == 0 {
+            return Err("Deck needs at least one card and a hand size".to_owned());
+        }
+        for (i, card) in rules.ranks.iter().enumerate() {
+            if rules.ranks[..i].contains(card) {
+                return Err(format!("Card {} listed twice in deck", card));
+            }
+        }
+        for card in &rules.wildcards {
+            if !rules.ranks.contains(card) {
+                return Err(format!("Wildcard {} is not in the deck", card));
+            }
+        }
+        Ok(rules)
+    }
+    /// Part 1, J is a Jack between T and Q
+    pub fn standard() -> Self {
+        Self::make("Standard", "23456789TJQKA", "", 5).unwrap()
+    }
+    /// Part 2, J is a Joker, the weakest card but wild when working out the type
+    pub fn joker() -> Self {
+        Self::make("Joker", "J23456789TQKA", "J", 5).unwrap()
+    }
+    /// Real poker, hands are five rank and suit pairs such as "AsKsQsJsTs"
+    pub fn poker() -> Self {
+        Self {
+            ladder: Ladder::Poker,
+            ..Self::make("Poker", "23456789TJQKA", "", 5).unwrap()
+        }
     }
+    pub fn card_rank(&self, card: &char) -> Result<usize, String> {
+        self.ranks
+            .iter()
+            .position(|c| c == card)
+            .ok_or_else(|| format!("Card {} is not in the {} deck", card, self.name))
+    }
+    fn count_bits(&self) -> u32 {
+        usize::BITS - self.hand_size.leading_zeros()
+    }
+    fn rank_bits(&self) -> u32 {
+        usize::BITS - (self.ranks.len() - 1).leading_zeros()
+    }
+    fn sort_key_bits(&self) -> u32 {
+        self.hand_size as u32 * (self.count_bits() + self.rank_bits())
+    }
+}
+
+/// Hands are ordered by their type's groups, padded to the hand size, then each card's rank
+/// Packed into one integer when that fits, otherwise kept as the list of fields
+/// Every hand scored under one rule set uses the same variant
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum SortKey {
+    Packed(u128),
+    Wide(Vec<usize>),
 }
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct Hand {
     pub card_set: Vec<char>,
     pub og_card_set: Vec<char>,
-    distinct_cards: HashSet<char>,
     value: u64,
+    // Type and tie break ranks packed together, so ordering hands is one integer compare
+    // None until the hand is scored under some rules
+    sort_key: Option<SortKey>,
 }
 impl Hand {
     pub fn make(hand: &str, value: &str) -> Self {
@@ -48,161 +151,200 @@ impl Hand {
 
         Self {
             og_card_set: chars.clone(),
-            card_set: chars.clone(),
+            card_set: chars,
             value: value.parse().unwrap(),
-            distinct_cards: HashSet::from_iter(chars.iter().cloned()),
+            sort_key: None,
         }
     }
-    pub fn get_value(&self) -> u64 {
-        return self.value;
-    }
-    fn get_card_count(&self, card: &char) -> usize {
-        let mut count = 0;
-        for char in &self.card_set {
-            if *char == *card {
-                count += 1
-            }
+    /// Copy of this hand as dealt, scored under the given rules
+    pub fn with_rules(&self, rules: &RuleSet) -> Result<Self, String> {
+        if rules.ladder == Ladder::Poker {
+            let poker_hand = PokerHand::from_cards(&self.og_card_set, rules)?;
+            let mut hand = self.clone();
+            hand.card_set = hand.og_card_set.iter().step_by(2).cloned().collect();
+            hand.sort_key = Some(SortKey::Packed(poker_hand.sort_key(rules.rank_bits())));
+            return Ok(hand);
         }
-        count
+        if self.og_card_set.len() != rules.hand_size {
+            return Err(format!(
+                "Hand {} has {} cards, {} expects {}",
+                self.og_card_set.iter().collect::<String>(),
+                self.og_card_set.len(),
+                rules.name,
+                rules.hand_size
+            ));
+        }
+        let mut hand = self.clone();
+        hand.card_set = hand.og_card_set.clone();
+        hand.promote_jokers(&rules.wildcards);
+        hand.sort_key = Some(hand.build_sort_key(rules)?);
+        Ok(hand)
     }
-    pub fn promote_joker(&mut self) {
-        if !self.distinct_cards.contains(&'J') {
-            return;
+    /// Pack the type's groups above the rank of each card as dealt
+    /// Groups are padded out to the hand size so every key has the same layout
+    fn build_sort_key(&self, rules: &RuleSet) -> Result<SortKey, String> {
+        let groups = self.get_type().groups;
+        let mut fields: Vec<usize> = (0..rules.hand_size)
+            .map(|i| *groups.get(i).unwrap_or(&0))
+            .collect();
+        for card in &self.og_card_set {
+            fields.push(rules.card_rank(card)?);
         }
-        //Promote J
-        let mut most_common_char = *self.card_set.first().unwrap();
-        let mut most_common_count = 0;
-        for c in &self.distinct_cards {
-            if c != &'J' {
-                let count = self.card_set.iter().filter(|&n| n == c).count();
-                if count > most_common_count {
-                    most_common_count = count;
-                    most_common_char = *c;
-                }
-            }
+        if rules.sort_key_bits() > u128::BITS {
+            return Ok(SortKey::Wide(fields));
         }
-        // println!(
-        //     "Replacing J {:?} -> {:?} [{:?}]",
-        //     self.card_set, most_common_char, self.distinct_cards
-        // );
-        if self.distinct_cards.len() > 1 {
-            self.distinct_cards.remove(&'J');
-        }
-        for i in 0..self.card_set.len() {
-            if self.card_set[i] == 'J' {
-                self.card_set[i] = most_common_char;
-            }
+        let mut key: u128 = 0;
+        for (i, field) in fields.iter().enumerate() {
+            let bits = if i < rules.hand_size {
+                rules.count_bits()
+            } else {
+                rules.rank_bits()
+            };
+            key = (key << bits) | *field as u128;
         }
+        Ok(SortKey::Packed(key))
     }
-    pub fn get_type(&self) -> CardType {
-        if self.distinct_cards.len() == 1 {
-            return CardType::FiveOfAKind;
-        }
-        if self.distinct_cards.len() == 2 {
-            let set: Vec<&char> = self.distinct_cards.iter().collect();
-            for c in &set {
-                if self.get_card_count(c) == 4 {
-                    return CardType::FourOfAKind;
-                }
-            }
-            if self.get_card_count(set[0]) == 3 && self.get_card_count(set[1]) == 2 {
-                return CardType::FullHouse;
-            }
-            if self.get_card_count(set[1]) == 3 && self.get_card_count(set[0]) == 2 {
-                return CardType::FullHouse;
-            }
+    /// Only comparable between hands scored under the same rules
+    pub fn get_sort_key(&self) -> Option<&SortKey> {
+        self.sort_key.as_ref()
+    }
+    pub fn get_value(&self) -> u64 {
+        self.value
+    }
+    /// Turn every wildcard into the most common other card, which always gives the best type
+    pub fn promote_jokers(&mut self, jokers: &[char]) {
+        if !self.card_set.iter().any(|c| jokers.contains(c)) {
+            return;
         }
-        if self.distinct_cards.len() == 3 {
-            let set: Vec<&char> = self.distinct_cards.iter().collect();
-            for c in &set {
-                if self.get_card_count(c) == 3 {
-                    return CardType::ThreeOfAKind;
-                }
-            }
-            if self.get_card_count(set[0]) == 2 && self.get_card_count(set[1]) == 2 {
-                return CardType::TwoPair;
-            }
-            if self.get_card_count(set[0]) == 2 && self.get_card_count(set[2]) == 2 {
-                return CardType::TwoPair;
-            }
-            if self.get_card_count(set[1]) == 2 && self.get_card_count(set[2]) == 2 {
-                return CardType::TwoPair;
-            }
+        let mut counts: HashMap<char, usize> = HashMap::new();
+        for card in self.card_set.iter().filter(|c| !jokers.contains(c)) {
+            *counts.entry(*card).or_default() += 1;
         }
-        let set: Vec<&char> = self.distinct_cards.iter().collect();
-        for c in set {
-            if self.get_card_count(c) == 2 {
-                return CardType::OnePair;
+        // If the hand is all wildcards, they all become the same one
+        let most_common_char = counts
+            .iter()
+            .max_by_key(|(card, count)| (**count, **card))
+            .map(|(card, _)| *card)
+            .unwrap_or(jokers[0]);
+        for card in self.card_set.iter_mut() {
+            if jokers.contains(card) {
+                *card = most_common_char;
             }
         }
-        CardType::HighCard
     }
-}
-impl Ord for Hand {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        let self_type = self.get_type();
-        let other_type = other.get_type();
-        if self_type != other_type {
-            return self_type.cmp(&other_type);
-        }
-        //if its the same type then we walk the chars in order
-        for (c1, c2) in self.og_card_set.iter().zip(other.og_card_set.iter()) {
-            if c1 != c2 {
-                return card_to_virtual_rank(c1).cmp(&card_to_virtual_rank(c2));
-            }
-        }
-        std::cmp::Ordering::Equal
+    pub fn get_type(&self) -> CardType {
+        CardType::from_cards(&self.card_set)
     }
-}
-impl PartialOrd for Hand {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    pub fn get_type_name(&self, rules: &RuleSet) -> String {
+        match rules.ladder {
+            Ladder::CamelCards => self.get_type().get_name(),
+            Ladder::Poker => match PokerHand::from_cards(&self.og_card_set, rules) {
+                Ok(poker_hand) => poker_hand.category.get_name().to_owned(),
+                Err(e) => e,
+            },
+        }
     }
 }
 
-fn card_to_virtual_rank(a: &char) -> usize {
-    match a {
-        'A' => 13,
-        'K' => 12,
-        'Q' => 11,
-        'T' => 10,
-        '9' => 9,
-        '8' => 8,
-        '7' => 7,
-        '6' => 6,
-        '5' => 5,
-        '4' => 4,
-        '3' => 3,
-        '2' => 2,
-        'J' => 1,
-        _ => panic!("Unhandled card {}", a),
+fn total_winnings(dealt: &[Hand], rules: &RuleSet) -> Result<u64, String> {
+    let mut hands = dealt
+        .iter()
+        .map(|h| h.with_rules(rules))
+        .collect::<Result<Vec<Hand>, String>>()?;
+    //Sort hands lowest to highest
+    hands.sort_by(|a, b| a.get_sort_key().cmp(&b.get_sort_key()));
+    let mut sum = 0;
+    for (i, hand) in hands.iter().enumerate() {
+        sum += hand.get_value() * (i as u64 + 1)
     }
+    if let Some(best) = hands.last() {
+        println!(
+            "{} best hand {} ({})",
+            rules.name,
+            best.og_card_set.iter().collect::<String>(),
+            best.get_type_name(rules)
+        );
+    }
+    Ok(sum)
 }
 
-fn read_file(filename: &str) -> u64 {
+fn read_file(filename: &str) -> Vec<Hand> {
     let mut hands = Vec::new();
     let file_contents = read_to_string(filename).unwrap();
     let lines: Vec<&str> = file_contents.lines().collect();
     for line in lines {
         let sp: Vec<&str> = line.split(' ').collect();
-        let mut hand = Hand::make(sp[0], sp[1]);
-        hand.promote_joker();
-        hands.push(hand);
-    }
-    //Sort hands lowest to highest
-    hands.sort();
-    for h in &hands {
-        println!("{:?}->{:?}", h.card_set, h.get_type())
+        hands.push(Hand::make(sp[0], sp[1]));
     }
-    let mut sum = 0;
-    for i in 0..hands.len() {
-        sum += hands[i].get_value() * (i as u64 + 1)
+    hands
+}
+
+/// Rule sets named on the command line, or both Camel Cards rule sets if none given
+fn rule_sets_from_args(args: &[String]) -> Result<Vec<RuleSet>, String> {
+    match args.first() {
+        Some(ladder) if ladder == "poker" => Ok(vec![RuleSet::poker()]),
+        Some(ranks) => {
+            let wildcards = args.get(1).map(|s| s.as_str()).unwrap_or("");
+            let hand_size = args.get(2).map(|s| s.parse().unwrap()).unwrap_or(5);
+            Ok(vec![RuleSet::make("Custom", ranks, wildcards, hand_size)?])
+        }
+        None => Ok(vec![RuleSet::standard(), RuleSet::joker()]),
     }
-    sum
 }
 
 fn main() {
-    let line_results = read_file("input");
+    // Usage: day7 [input] [ranks weakest first] [wildcards] [hand size]
+    //        day7 [input] poker
+    //        day7 --stats [ranks weakest first] [wildcards] [hand size]
+    //        day7 --simulate <hand> <bid> [field size] [trials] [seed]
+    // With a deck given, only that variant is scored
+    // Poker hands are written as rank and suit pairs, "AsKsQsJsTs 100"
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|s| s.as_str()) {
+        Some("--stats") => {
+            match rule_sets_from_args(&args[1..]) {
+                Ok(rule_sets) => rule_sets.iter().for_each(stats::print_type_table),
+                Err(e) => println!("Bad deck: {}", e),
+            }
+            return;
+        }
+        Some("--simulate") => {
+            let hand = Hand::make(&args[1], &args[2]);
+            let field_size = args.get(3).map(|s| s.parse().unwrap()).unwrap_or(1000);
+            let trials = args.get(4).map(|s| s.parse().unwrap()).unwrap_or(100);
+            let seed = args.get(5).map(|s| s.parse().unwrap()).unwrap_or(7);
+            for rules in [RuleSet::standard(), RuleSet::joker()] {
+                let mut rng = stats::XorShift::new(seed);
+                match stats::simulate(&hand, &rules, field_size, trials, &mut rng) {
+                    Ok(result) => println!(
+                        "{}: {} beats {:.2}% of hands, expected rank {:.1} of {}, expected winnings {:.1}",
+                        rules.name,
+                        args[1],
+                        result.win_rate * 100.0,
+                        result.expected_rank,
+                        field_size + 1,
+                        result.expected_winnings
+                    ),
+                    Err(e) => println!("{} failed: {}", rules.name, e),
+                }
+            }
+            return;
+        }
+        _ => {}
+    }
+    let hands = read_file(args.first().map(|s| s.as_str()).unwrap_or("input"));
 
-    println!("Total {}", line_results);
+    let rule_sets = match rule_sets_from_args(args.get(1..).unwrap_or_default()) {
+        Ok(rule_sets) => rule_sets,
+        Err(e) => {
+            println!("Bad deck: {}", e);
+            return;
+        }
+    };
+    for rules in &rule_sets {
+        match total_winnings(&hands, rules) {
+            Ok(total) => println!("{} Total {}", rules.name, total),
+            Err(e) => println!("{} failed: {}", rules.name, e),
+        }
+    }
 }
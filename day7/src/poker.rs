@@ -0,0 +1,104 @@
+use crate::RuleSet;
+
+/// Standard poker hand categories, weakest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PokerCategory {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+}
+impl PokerCategory {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            PokerCategory::HighCard => "High card",
+            PokerCategory::OnePair => "One pair",
+            PokerCategory::TwoPair => "Two pair",
+            PokerCategory::ThreeOfAKind => "Three of a kind",
+            PokerCategory::Straight => "Straight",
+            PokerCategory::Flush => "Flush",
+            PokerCategory::FullHouse => "Full house",
+            PokerCategory::FourOfAKind => "Four of a kind",
+            PokerCategory::StraightFlush => "Straight flush",
+        }
+    }
+}
+
+/// A five card hand of suited cards, evaluated with the usual kicker rules
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PokerHand {
+    pub category: PokerCategory,
+    /// Ranks to compare in order once categories tie
+    pub kickers: Vec<usize>,
+}
+impl PokerHand {
+    /// `cards` is rank then suit for each card, so "AsKs" is the ace and king of spades
+    pub fn from_cards(cards: &[char], rules: &RuleSet) -> Result<Self, String> {
+        if cards.len() != 10 {
+            return Err(format!(
+                "Poker hand {} needs 5 rank and suit pairs",
+                cards.iter().collect::<String>()
+            ));
+        }
+        let mut ranks = Vec::new();
+        for (i, pair) in cards.chunks(2).enumerate() {
+            if cards[..i * 2].chunks(2).any(|other| other == pair) {
+                return Err(format!("Card {}{} dealt twice", pair[0], pair[1]));
+            }
+            ranks.push(rules.card_rank(&pair[0])?);
+        }
+        let is_flush = cards.chunks(2).all(|pair| pair[1] == cards[1]);
+
+        // Group the ranks, biggest group first then highest rank, which is also the kicker order
+        let mut groups: Vec<(usize, usize)> = Vec::new();
+        for rank in &ranks {
+            match groups.iter_mut().find(|(_, r)| r == rank) {
+                Some((count, _)) => *count += 1,
+                None => groups.push((1, *rank)),
+            }
+        }
+        groups.sort_unstable_by(|a, b| b.cmp(a));
+        let mut kickers: Vec<usize> = groups.iter().map(|(_, rank)| *rank).collect();
+
+        let mut straight_high = None;
+        if groups.len() == 5 {
+            if kickers[0] - kickers[4] == 4 {
+                straight_high = Some(kickers[0]);
+            } else if kickers[0] == rules.ranks.len() - 1 && kickers[1] == 3 {
+                // The ace plays low in A 2 3 4 5, so the straight is five high
+                straight_high = Some(3);
+            }
+        }
+        if let Some(high) = straight_high {
+            kickers = vec![high];
+        }
+
+        let counts: Vec<usize> = groups.iter().map(|(count, _)| *count).collect();
+        let category = match (counts.as_slice(), straight_high.is_some(), is_flush) {
+            (_, true, true) => PokerCategory::StraightFlush,
+            ([4, 1], _, _) => PokerCategory::FourOfAKind,
+            ([3, 2], _, _) => PokerCategory::FullHouse,
+            (_, _, true) => PokerCategory::Flush,
+            (_, true, _) => PokerCategory::Straight,
+            ([3, 1, 1], _, _) => PokerCategory::ThreeOfAKind,
+            ([2, 2, 1], _, _) => PokerCategory::TwoPair,
+            ([2, 1, 1, 1], _, _) => PokerCategory::OnePair,
+            ([1, 1, 1, 1, 1], _, _) => PokerCategory::HighCard,
+            _ => return Err("Five of a kind isn't a poker hand".to_owned()),
+        };
+        Ok(Self { category, kickers })
+    }
+    /// Category above the kickers, padded to five so every key has the same layout
+    pub fn sort_key(&self, rank_bits: u32) -> u128 {
+        let mut key = self.category as u128;
+        for i in 0..5 {
+            key = (key << rank_bits) | *self.kickers.get(i).unwrap_or(&0) as u128;
+        }
+        key
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{CardType, Hand, Ladder, RuleSet};
+use std::collections::BTreeMap;
+
+/// How many of the deck^hand_size possible deals land on each type
+/// Every multiset of ranks is visited once and weighted by the number of ways it can be dealt
+pub fn type_counts(rules: &RuleSet, promote: bool) -> BTreeMap<CardType, u128> {
+    let mut counts: BTreeMap<CardType, u128> = BTreeMap::new();
+    let mut rank_counts = vec![0; rules.ranks.len()];
+    visit_multisets(&mut rank_counts, 0, rules.hand_size, &mut |rank_counts| {
+        let cards: String = rank_counts
+            .iter()
+            .enumerate()
+            .flat_map(|(rank, count)| std::iter::repeat_n(rules.ranks[rank], *count))
+            .collect();
+        let mut hand = Hand::make(&cards, "0");
+        if promote {
+            hand.promote_jokers(&rules.wildcards);
+        }
+        *counts.entry(hand.get_type()).or_default() += orderings(rank_counts);
+    });
+    counts
+}
+
+fn visit_multisets(
+    rank_counts: &mut Vec<usize>,
+    rank: usize,
+    remaining: usize,
+    visit: &mut dyn FnMut(&[usize]),
+) {
+    if rank == rank_counts.len() - 1 {
+        rank_counts[rank] = remaining;
+        visit(rank_counts);
+        rank_counts[rank] = 0;
+        return;
+    }
+    for count in 0..=remaining {
+        rank_counts[rank] = count;
+        visit_multisets(rank_counts, rank + 1, remaining - count, visit);
+    }
+    rank_counts[rank] = 0;
+}
+
+/// Multinomial coefficient, the number of distinct orders to deal these cards in
+fn orderings(rank_counts: &[usize]) -> u128 {
+    let mut res: u128 = 1;
+    let mut dealt: u128 = 0;
+    for count in rank_counts {
+        for i in 1..=*count as u128 {
+            dealt += 1;
+            // Multiplying then dividing stays exact, res * dealt / i is a binomial step
+            res = res * dealt / i;
+        }
+    }
+    res
+}
+
+pub fn print_type_table(rules: &RuleSet) {
+    if rules.ladder != Ladder::CamelCards {
+        println!(
+            "{}: type statistics only cover Camel Cards rule sets",
+            rules.name
+        );
+        return;
+    }
+    let total = (rules.ranks.len() as u128).pow(rules.hand_size as u32);
+    let plain = type_counts(rules, false);
+    let promoted = type_counts(rules, true);
+    println!("{} ({} possible deals)", rules.name, total);
+    println!(
+        "{:>24} {:>12} {:>10} {:>12} {:>10}",
+        "type", "dealt", "%", "promoted", "%"
+    );
+    let mut types: Vec<&CardType> = plain.keys().chain(promoted.keys()).collect();
+    types.sort();
+    types.dedup();
+    for card_type in types.iter().rev() {
+        let dealt = *plain.get(card_type).unwrap_or(&0);
+        let with_promotion = *promoted.get(card_type).unwrap_or(&0);
+        println!(
+            "{:>24} {:>12} {:>10.4} {:>12} {:>10.4}",
+            card_type.get_name(),
+            dealt,
+            dealt as f64 / total as f64 * 100.0,
+            with_promotion,
+            with_promotion as f64 / total as f64 * 100.0
+        );
+    }
+}
+
+/// Small xorshift generator so simulations are repeatable without extra crates
+pub struct XorShift {
+    state: u64,
+}
+impl XorShift {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed.max(1) }
+    }
+    pub fn next_below(&mut self, limit: usize) -> usize {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state % limit as u64) as usize
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulationResult {
+    pub expected_rank: f64,
+    pub expected_winnings: f64,
+    /// Fraction of random hands this hand beats
+    pub win_rate: f64,
+}
+
+/// Monte Carlo estimate of where `hand` lands among `field_size` other hands dealt at random
+/// Hands identical to ours are ranked below it
+pub fn simulate(
+    hand: &Hand,
+    rules: &RuleSet,
+    field_size: usize,
+    trials: usize,
+    rng: &mut XorShift,
+) -> Result<SimulationResult, String> {
+    let scored = hand.with_rules(rules)?;
+    let ours = scored.get_sort_key();
+    let mut beaten_total = 0u64;
+    for _ in 0..trials {
+        for _ in 0..field_size {
+            let cards: String = (0..rules.hand_size)
+                .map(|_| rules.ranks[rng.next_below(rules.ranks.len())])
+                .collect();
+            let other = Hand::make(&cards, "0").with_rules(rules)?;
+            if other.get_sort_key() <= ours {
+                beaten_total += 1;
+            }
+        }
+    }
+    let deals = (trials * field_size).max(1) as f64;
+    let win_rate = beaten_total as f64 / deals;
+    let expected_rank = 1.0 + win_rate * field_size as f64;
+    Ok(SimulationResult {
+        expected_rank,
+        expected_winnings: expected_rank * hand.get_value() as f64,
+        win_rate,
+    })
+}
@@ -0,0 +1,240 @@
+use crate::{Direction, NodeId, StepMap};
+use std::collections::VecDeque;
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Both exits of a node, merged into one labelled edge when they go to the same place
+fn edges(network: &StepMap, node: NodeId) -> Vec<(NodeId, &'static str)> {
+    let left = network.get_value(node, Direction::Left);
+    let right = network.get_value(node, Direction::Right);
+    if left == right {
+        vec![(left, "LR")]
+    } else {
+        vec![(left, "L"), (right, "R")]
+    }
+}
+
+pub fn to_dot(network: &StepMap) -> String {
+    let mut res = String::from("digraph network {\n");
+    for node in network.get_nodes() {
+        let style = match (network.is_start(node), network.is_end(node)) {
+            (true, true) => " [shape=doublecircle, style=filled, fillcolor=gold]",
+            (true, false) => " [shape=box, style=filled, fillcolor=palegreen]",
+            (false, true) => " [shape=doublecircle, style=filled, fillcolor=salmon]",
+            (false, false) => "",
+        };
+        res += &format!("  \"{}\"{};\n", network.get_name(node), style);
+    }
+    for node in network.get_nodes() {
+        for (to, label) in edges(network, node) {
+            res += &format!(
+                "  \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                network.get_name(node),
+                network.get_name(to),
+                label
+            );
+        }
+    }
+    res += "}\n";
+    res
+}
+
+pub fn to_graphml(network: &StepMap) -> String {
+    let mut res = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"start\" for=\"node\" attr.name=\"start\" attr.type=\"boolean\"/>\n",
+        "  <key id=\"end\" for=\"node\" attr.name=\"end\" attr.type=\"boolean\"/>\n",
+        "  <key id=\"dir\" for=\"edge\" attr.name=\"direction\" attr.type=\"string\"/>\n",
+        "  <graph id=\"network\" edgedefault=\"directed\">\n"
+    ));
+    for node in network.get_nodes() {
+        res += &format!(
+            "    <node id=\"{}\"><data key=\"start\">{}</data><data key=\"end\">{}</data></node>\n",
+            xml_escape(network.get_name(node)),
+            network.is_start(node),
+            network.is_end(node)
+        );
+    }
+    for node in network.get_nodes() {
+        for (to, label) in edges(network, node) {
+            res += &format!(
+                "    <edge source=\"{}\" target=\"{}\"><data key=\"dir\">{}</data></edge>\n",
+                xml_escape(network.get_name(node)),
+                xml_escape(network.get_name(to)),
+                label
+            );
+        }
+    }
+    res += "  </graph>\n</graphml>\n";
+    res
+}
+
+/// Tarjan's strongly connected components, iterative so huge networks don't blow the stack
+pub fn strongly_connected_components(network: &StepMap) -> Vec<Vec<NodeId>> {
+    const UNVISITED: usize = usize::MAX;
+    let node_count = network.get_node_count();
+    let mut index = vec![UNVISITED; node_count];
+    let mut low_link = vec![0; node_count];
+    let mut on_stack = vec![false; node_count];
+    let mut stack: Vec<NodeId> = Vec::new();
+    let mut components = Vec::new();
+    let mut next_index = 0;
+
+    for root in network.get_nodes() {
+        if index[root as usize] != UNVISITED {
+            continue;
+        }
+        // Each frame is a node and which of its two exits to look at next
+        let mut call_stack: Vec<(NodeId, usize)> = vec![(root, 0)];
+        index[root as usize] = next_index;
+        low_link[root as usize] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root as usize] = true;
+
+        while let Some((node, exit)) = call_stack.pop() {
+            let n = node as usize;
+            if exit < 2 {
+                call_stack.push((node, exit + 1));
+                let dir = if exit == 0 {
+                    Direction::Left
+                } else {
+                    Direction::Right
+                };
+                let to = network.get_value(node, dir);
+                let t = to as usize;
+                if index[t] == UNVISITED {
+                    index[t] = next_index;
+                    low_link[t] = next_index;
+                    next_index += 1;
+                    stack.push(to);
+                    on_stack[t] = true;
+                    call_stack.push((to, 0));
+                } else if on_stack[t] {
+                    low_link[n] = low_link[n].min(index[t]);
+                }
+                continue;
+            }
+            // Both exits done, pass our low link back up and close the component if we're its root
+            if let Some((parent, _)) = call_stack.last() {
+                let p = *parent as usize;
+                low_link[p] = low_link[p].min(low_link[n]);
+            }
+            if low_link[n] == index[n] {
+                let mut component = Vec::new();
+                loop {
+                    let member = stack.pop().unwrap();
+                    on_stack[member as usize] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+    components
+}
+
+/// Every node reachable from `start`, ignoring the instruction order
+pub fn reachable_from(network: &StepMap, start: NodeId) -> Vec<bool> {
+    let mut seen = vec![false; network.get_node_count()];
+    let mut queue = VecDeque::from([start]);
+    seen[start as usize] = true;
+    while let Some(node) = queue.pop_front() {
+        for (to, _) in edges(network, node) {
+            if !seen[to as usize] {
+                seen[to as usize] = true;
+                queue.push_back(to);
+            }
+        }
+    }
+    seen
+}
+
+pub fn print_analysis(network: &StepMap) {
+    let components = strongly_connected_components(network);
+    let mut component_of = vec![0; network.get_node_count()];
+    for (i, component) in components.iter().enumerate() {
+        for node in component {
+            component_of[*node as usize] = i;
+        }
+    }
+    // A single node only counts as a cycle if it steps to itself
+    let is_cyclic: Vec<bool> = components
+        .iter()
+        .map(|c| c.len() > 1 || edges(network, c[0]).iter().any(|(to, _)| *to == c[0]))
+        .collect();
+    println!(
+        "{} nodes, {} strongly connected components, {} of them cyclic",
+        network.get_node_count(),
+        components.len(),
+        is_cyclic.iter().filter(|c| **c).count()
+    );
+    for (component, _) in components.iter().zip(&is_cyclic).filter(|(_, c)| **c) {
+        let ends: Vec<&str> = component
+            .iter()
+            .filter(|n| network.is_end(**n))
+            .map(|n| network.get_name(*n))
+            .collect();
+        println!(
+            "  Component of {} nodes containing ends {:?}",
+            component.len(),
+            ends
+        );
+    }
+
+    let mut reached_by_any = vec![false; network.get_node_count()];
+    let mut one_end_each = true;
+    for start in network.get_starts() {
+        let reachable = reachable_from(network, start);
+        let ends: Vec<&str> = network
+            .get_nodes()
+            .filter(|n| reachable[*n as usize] && network.is_end(*n))
+            .map(|n| network.get_name(n))
+            .collect();
+        let mut cycles: Vec<usize> = network
+            .get_nodes()
+            .filter(|n| reachable[*n as usize])
+            .map(|n| component_of[n as usize])
+            .filter(|c| is_cyclic[*c])
+            .collect();
+        cycles.sort_unstable();
+        cycles.dedup();
+        println!(
+            "Start {} reaches {} nodes, ends {:?}, {} cyclic component(s)",
+            network.get_name(start),
+            reachable.iter().filter(|r| **r).count(),
+            ends,
+            cycles.len()
+        );
+        one_end_each &= ends.len() == 1;
+        for (seen, reached) in reachable.iter().zip(reached_by_any.iter_mut()) {
+            *reached |= *seen;
+        }
+    }
+    let unreachable: Vec<&str> = network
+        .get_nodes()
+        .filter(|n| !reached_by_any[*n as usize])
+        .map(|n| network.get_name(n))
+        .collect();
+    println!("{} nodes unreachable from any start", unreachable.len());
+    if !unreachable.is_empty() && unreachable.len() <= 50 {
+        println!("  {:?}", unreachable);
+    }
+    if one_end_each {
+        println!("Every start reaches exactly one end, so the LCM shortcut can apply if each loop hits it once");
+    } else {
+        println!(
+            "Some start reaches zero or several ends, so the plain LCM shortcut doesn't apply"
+        );
+    }
+}
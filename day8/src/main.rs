@@ -1,129 +1,476 @@
+mod graph;
+
+use num_bigint::{BigInt, BigUint};
+use num_traits::{One, Zero};
+use number_theory::{combine_congruences, lowest_common_multiple_of_set};
+use regex::Regex;
 use std::{
-    collections::{HashMap, HashSet},
-    fs::read_to_string,
+    collections::HashMap,
+    env,
+    fs::{read_to_string, write},
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Direction {
-    Left,
-    Right,
+    Left = 0,
+    Right = 1,
 }
 
 impl Direction {
-    fn from_char(c: char) -> Self {
+    fn from_char(c: char) -> Option<Self> {
         match c {
-            'L' => Direction::Left,
-            'R' => Direction::Right,
-            _ => panic!("Unhandled dir"),
+            'L' => Some(Direction::Left),
+            'R' => Some(Direction::Right),
+            _ => None,
+        }
+    }
+}
+
+type NodeId = u32;
+
+/// Picks out the start or end nodes of a walk by name
+#[derive(Debug, Clone)]
+enum NodeMatcher {
+    Exact(String),
+    Suffix(String),
+    Pattern(Regex),
+}
+
+impl NodeMatcher {
+    pub fn parse(kind: &str, value: &str) -> Result<Self, String> {
+        match kind {
+            "exact" => Ok(NodeMatcher::Exact(value.to_owned())),
+            "suffix" => Ok(NodeMatcher::Suffix(value.to_owned())),
+            "regex" => Regex::new(value)
+                .map(NodeMatcher::Pattern)
+                .map_err(|e| e.to_string()),
+            _ => Err(format!(
+                "Unknown matcher {}, use exact, suffix or regex",
+                kind
+            )),
+        }
+    }
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            NodeMatcher::Exact(exact) => name == exact,
+            NodeMatcher::Suffix(suffix) => name.ends_with(suffix.as_str()),
+            NodeMatcher::Pattern(pattern) => pattern.is_match(name),
         }
     }
 }
 
+/// Which nodes to start from and finish on, and how to find the answer
+#[derive(Debug, Clone)]
+struct WalkMode {
+    name: String,
+    start: NodeMatcher,
+    end: NodeMatcher,
+    /// Step every walker one at a time instead of solving cycles, up to this many steps
+    simulate: Option<usize>,
+}
+
+impl WalkMode {
+    /// Part 1, one walker from AAA to exactly ZZZ
+    pub fn single() -> Self {
+        Self {
+            name: "Single".to_owned(),
+            start: NodeMatcher::Exact("AAA".to_owned()),
+            end: NodeMatcher::Exact("ZZZ".to_owned()),
+            simulate: None,
+        }
+    }
+    /// Part 2, a ghost on every node ending in A, all needing to be on nodes ending in Z
+    pub fn ghosts() -> Self {
+        Self {
+            name: "Ghosts".to_owned(),
+            start: NodeMatcher::Suffix("A".to_owned()),
+            end: NodeMatcher::Suffix("Z".to_owned()),
+            simulate: None,
+        }
+    }
+}
+
+/// The whole desert network, with node names interned to ids
+/// Both exits of node n live side by side at `steps[2n]` (left) and `steps[2n + 1]` (right)
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct StepMap {
-    pub left_step: String,
-    pub right_step: String,
+    names: Vec<String>,
+    ids: HashMap<String, NodeId>,
+    steps: Vec<NodeId>,
+    is_start: Vec<bool>,
+    is_end: Vec<bool>,
 }
 
 impl StepMap {
-    pub fn make(value: &str) -> Self {
-        let mut cleaned = value.replace('(', "");
-        cleaned = cleaned.replace(')', "");
-        let chars: Vec<&str> = cleaned.split(',').collect();
-        let left = chars[0].trim();
-        let right = chars[1].trim();
-
-        Self {
-            left_step: left.to_owned(),
-            right_step: right.to_owned(),
+    pub fn from_lines(lines: &[&str]) -> Result<Self, String> {
+        let mut network = Self {
+            names: Vec::new(),
+            ids: HashMap::new(),
+            steps: Vec::new(),
+            is_start: Vec::new(),
+            is_end: Vec::new(),
+        };
+        let mut defined: Vec<bool> = Vec::new();
+        for line in lines {
+            if line.is_empty() {
+                continue;
+            }
+            let Some((name, exits)) = line.split_once('=') else {
+                return Err(format!("Bad node line {}", line));
+            };
+            let key = network.intern(name.trim());
+            let mut cleaned = exits.trim().replace('(', "");
+            cleaned = cleaned.replace(')', "");
+            let Some((left, right)) = cleaned.split_once(',') else {
+                return Err(format!("Bad node line {}", line));
+            };
+            let left = network.intern(left.trim());
+            let right = network.intern(right.trim());
+            network.steps[key as usize * 2] = left;
+            network.steps[key as usize * 2 + 1] = right;
+            defined.resize(network.get_node_count(), false);
+            defined[key as usize] = true;
+        }
+        defined.resize(network.get_node_count(), false);
+        let undefined: Vec<&str> = network
+            .get_nodes()
+            .filter(|n| !defined[*n as usize])
+            .map(|n| network.get_name(n))
+            .collect();
+        if !undefined.is_empty() {
+            return Err(format!(
+                "Nodes stepped to but never defined: {:?}",
+                undefined
+            ));
         }
+        network.set_matchers(
+            &NodeMatcher::Suffix("A".to_owned()),
+            &NodeMatcher::Suffix("Z".to_owned()),
+        );
+        Ok(network)
     }
-    pub fn get_value(&self, step: &Direction) -> &str {
-        match step {
-            Direction::Left => &self.left_step,
-            Direction::Right => &self.right_step,
+    /// Recompute the start and end flags for a different kind of walk
+    pub fn set_matchers(&mut self, start: &NodeMatcher, end: &NodeMatcher) {
+        self.is_start = self.names.iter().map(|n| start.matches(n)).collect();
+        self.is_end = self.names.iter().map(|n| end.matches(n)).collect();
+    }
+    fn intern(&mut self, name: &str) -> NodeId {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
         }
+        let id = self.names.len() as NodeId;
+        self.names.push(name.to_owned());
+        self.ids.insert(name.to_owned(), id);
+        // Placeholder exits until the node's own line is read
+        self.steps.extend([id, id]);
+        id
+    }
+    #[inline]
+    pub fn get_value(&self, node: NodeId, step: Direction) -> NodeId {
+        self.steps[node as usize * 2 + step as usize]
+    }
+    pub fn get_name(&self, node: NodeId) -> &str {
+        &self.names[node as usize]
+    }
+    pub fn get_node_count(&self) -> usize {
+        self.names.len()
+    }
+    pub fn get_nodes(&self) -> impl Iterator<Item = NodeId> {
+        0..self.names.len() as NodeId
+    }
+    pub fn is_start(&self, node: NodeId) -> bool {
+        self.is_start[node as usize]
+    }
+    pub fn is_end(&self, node: NodeId) -> bool {
+        self.is_end[node as usize]
+    }
+    pub fn get_starts(&self) -> Vec<NodeId> {
+        self.get_nodes().filter(|n| self.is_start(*n)).collect()
     }
 }
 
-fn find_steps_until_terminated_z(
-    steps: &HashMap<&str, StepMap>,
-    instructions: &[Direction],
-    start: &str,
-) -> usize {
-    let mut step_count = 0;
+/// How one ghost moves forever: a tail that is walked once, then a cycle that repeats
+/// Z offsets are step counts from the start, so cycle hits recur every `cycle_len` steps
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GhostCycle {
+    tail_len: usize,
+    cycle_len: usize,
+    tail_z_hits: Vec<usize>,
+    cycle_z_hits: Vec<usize>,
+}
+impl GhostCycle {
+    pub fn is_on_z_at(&self, step: usize) -> bool {
+        if step < self.tail_len {
+            self.tail_z_hits.contains(&step)
+        } else {
+            let offset = self.tail_len + (step - self.tail_len) % self.cycle_len;
+            self.cycle_z_hits.contains(&offset)
+        }
+    }
+}
+
+/// Walk until a (node, instruction index) state repeats, which is where the cycle closes
+fn find_cycle(network: &StepMap, instructions: &[Direction], start: NodeId) -> GhostCycle {
+    let mut first_seen: HashMap<(NodeId, usize), usize> = HashMap::new();
+    let mut z_hits = Vec::new();
     let mut cur = start;
-    for dir in instructions.iter().cycle() {
-        cur = steps[cur].get_value(dir);
+    let mut step_count = 0;
+    loop {
+        let instruction_index = step_count % instructions.len();
+        if let Some(seen_at) = first_seen.insert((cur, instruction_index), step_count) {
+            let (tail_z_hits, cycle_z_hits) = z_hits.iter().partition(|hit| **hit < seen_at);
+            return GhostCycle {
+                tail_len: seen_at,
+                cycle_len: step_count - seen_at,
+                tail_z_hits,
+                cycle_z_hits,
+            };
+        }
+        if network.is_end(cur) {
+            z_hits.push(step_count);
+        }
+        cur = network.get_value(cur, instructions[instruction_index]);
         step_count += 1;
-        if cur.ends_with("Z") {
-            break;
+    }
+}
+
+/// Walk every start in lock step until they are all on an end node together
+fn simulate_walk(
+    network: &StepMap,
+    instructions: &[Direction],
+    starts: &[NodeId],
+    max_steps: usize,
+) -> Option<usize> {
+    let mut walkers = starts.to_vec();
+    for (step_count, dir) in instructions.iter().cycle().enumerate().take(max_steps) {
+        for walker in walkers.iter_mut() {
+            *walker = network.get_value(*walker, *dir);
+        }
+        if walkers.iter().all(|w| network.is_end(*w)) {
+            return Some(step_count + 1);
         }
     }
-    step_count
+    None
 }
 
-fn read_file(filename: &str) -> usize {
-    let mut steps: HashMap<&str, StepMap> = HashMap::new();
+/// First step (after leaving the start) where every ghost is on a Z node at once
+fn find_alignment(ghosts: &[GhostCycle]) -> Option<BigUint> {
+    // Check the early steps directly, this covers every tail hit and one full lap of each cycle
+    let max_tail = ghosts.iter().map(|g| g.tail_len).max().unwrap_or(0);
+    let max_cycle = ghosts.iter().map(|g| g.cycle_len).max().unwrap_or(0);
+    let bound = max_tail + max_cycle;
+    if let Some(step) = (1..=bound).find(|step| ghosts.iter().all(|g| g.is_on_z_at(*step))) {
+        return Some(BigUint::from(step));
+    }
+    // Past that every ghost is cycling, so each cycle hit is a congruence
+    // Merge one ghost at a time, dropping repeats so the set only grows with distinct answers
+    // Residues come back reduced, so equal congruences compare equal
+    let mut pending: Vec<(BigInt, BigInt)> = vec![(BigInt::zero(), BigInt::one())];
+    for ghost in ghosts {
+        let cycle_len = BigInt::from(ghost.cycle_len);
+        let mut next = Vec::new();
+        for (residue, modulus) in &pending {
+            for hit in &ghost.cycle_z_hits {
+                if let Some((r, m)) =
+                    combine_congruences(residue, modulus, &BigInt::from(*hit), &cycle_len)
+                {
+                    next.push((r, m));
+                }
+            }
+        }
+        next.sort_unstable();
+        next.dedup();
+        pending = next;
+    }
+    // Smallest solution after the steps already checked
+    let bound = BigInt::from(bound);
+    pending
+        .into_iter()
+        .map(|(residue, modulus)| {
+            if residue > bound {
+                residue
+            } else {
+                &residue + (&bound - &residue) / &modulus * &modulus + &modulus
+            }
+        })
+        .min()
+        .and_then(|step| step.to_biguint())
+}
 
-    let file_contents = read_to_string(filename).unwrap();
-    let mut lines: Vec<&str> = file_contents.lines().collect();
-    let mut instructions = lines.remove(0);
-    instructions = instructions.trim();
+fn read_file(filename: &str) -> Result<(StepMap, Vec<Direction>), String> {
+    let file_contents =
+        read_to_string(filename).map_err(|e| format!("Can't read {}: {}", filename, e))?;
+    let mut lines = file_contents.lines();
+    let instructions = lines.next().unwrap_or_default().trim();
+    if instructions.is_empty() {
+        return Err("no instructions on the first line".to_owned());
+    }
     let instructions_lookup: Vec<Direction> = instructions
         .chars()
-        .map(|c| Direction::from_char(c))
-        .collect();
-
-    for line in lines {
-        if line.len() == 0 {
-            continue;
-        }
-        let sp: Vec<&str> = line.split('=').collect();
-        let key = sp[0].trim();
-        let value = sp[1].trim();
-        let hand = StepMap::make(&value);
-        steps.insert(key, hand);
-    }
-
-    let starting_points: Vec<&str> = steps
-        .keys()
-        .filter(|key| key.ends_with("A"))
-        .map(|v| v.to_owned())
-        .collect();
-    let mut minimal_loops: HashSet<usize> = HashSet::new();
-    for start in starting_points {
-        let smallest_loop = find_steps_until_terminated_z(&steps, &instructions_lookup, start);
-        println!("Start -> Loop: {} -> {}", start, smallest_loop);
-        minimal_loops.insert(smallest_loop);
-    }
-    let final_loops: Vec<usize> = minimal_loops.iter().map(|f| *f).collect();
-    // Each loop is the minimal number of steps required to go from "start" -> A valid endpoint.
-    // Once you have rolled off the end of the loop, its going to throw you to _somewhere_ else to start a new loop
-    // If we assume that the input shall hit an end target loop, and then continue to re-loop
-    // We are looking for all of these smaller loops to align
-    // Alignments occur at the first lined up common multiple
-    // We want the lower one
-    lowest_common_multiple_of_set(&final_loops)
+        .map(|c| Direction::from_char(c).ok_or_else(|| format!("bad direction {:?}", c)))
+        .collect::<Result<_, _>>()?;
+    let lines: Vec<&str> = lines.collect();
+
+    Ok((StepMap::from_lines(&lines)?, instructions_lookup))
 }
 
-fn main() {
-    let line_results = read_file("input");
+fn solve(
+    network: &mut StepMap,
+    instructions: &[Direction],
+    mode: &WalkMode,
+) -> Result<BigUint, String> {
+    network.set_matchers(&mode.start, &mode.end);
+    let starts = network.get_starts();
+    if starts.is_empty() {
+        return Err("no start nodes".to_owned());
+    }
+    if let Some(max_steps) = mode.simulate {
+        return simulate_walk(network, instructions, &starts, max_steps)
+            .map(BigUint::from)
+            .ok_or_else(|| format!("walkers not all on end nodes within {} steps", max_steps));
+    }
+    let mut ghosts = Vec::new();
+    for start in starts {
+        let ghost = find_cycle(network, instructions, start);
+        println!(
+            "Start {} -> tail {}, cycle {}, Z at {:?} then {:?} + n*{}",
+            network.get_name(start),
+            ghost.tail_len,
+            ghost.cycle_len,
+            ghost.tail_z_hits,
+            ghost.cycle_z_hits,
+            ghost.cycle_len
+        );
+        ghosts.push(ghost);
+    }
+    // Each ghost ends up going round a loop of (node, instruction) states, hitting Z at fixed offsets
+    // The ghosts line up on a step that satisfies one of those offsets for every ghost at once
+    // which is a Chinese remainder problem over the cycle lengths
+    // When every loop starts at step 0 and hits Z once at its end, that collapses to the LCM
+    if ghosts
+        .iter()
+        .all(|g| g.tail_z_hits.is_empty() && g.cycle_z_hits == [g.cycle_len])
+    {
+        let loops: Vec<u64> = ghosts.iter().map(|g| g.cycle_len as u64).collect();
+        println!(
+            "Loops are aligned, LCM shortcut gives {}",
+            lowest_common_multiple_of_set(&loops).unwrap()
+        );
+    }
+    find_alignment(&ghosts)
+        .ok_or_else(|| "the walkers never all stand on end nodes at the same time".to_owned())
+}
 
-    println!("Total {}", line_results);
+#[derive(Debug, Clone, Default)]
+struct Options {
+    filename: String,
+    modes: Vec<WalkMode>,
+    dot_file: Option<String>,
+    graphml_file: Option<String>,
+    analyse: bool,
 }
 
-pub fn lowest_common_multiple_of_set(nums: &[usize]) -> usize {
-    if nums.len() == 1 {
-        return nums[0];
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        filename: "input".to_owned(),
+        ..Default::default()
+    };
+    let mut custom = WalkMode::ghosts();
+    custom.name = "Custom".to_owned();
+    let mut is_custom = false;
+    let mut simulate = None;
+    let mut i = 0;
+    while i < args.len() {
+        let value = |offset: usize| {
+            args.get(i + offset)
+                .ok_or_else(|| format!("{} needs more arguments", args[i]))
+        };
+        match args[i].as_str() {
+            "--simulate" => {
+                // Optional step limit, defaults to a billion
+                match args.get(i + 1).and_then(|a| a.parse().ok()) {
+                    Some(limit) => {
+                        simulate = Some(limit);
+                        i += 1;
+                    }
+                    None => simulate = Some(1_000_000_000),
+                }
+            }
+            "--start" => {
+                custom.start = NodeMatcher::parse(value(1)?, value(2)?)?;
+                is_custom = true;
+                i += 2;
+            }
+            "--end" => {
+                custom.end = NodeMatcher::parse(value(1)?, value(2)?)?;
+                is_custom = true;
+                i += 2;
+            }
+            "--dot" => {
+                options.dot_file = Some(value(1)?.to_owned());
+                i += 1;
+            }
+            "--graphml" => {
+                options.graphml_file = Some(value(1)?.to_owned());
+                i += 1;
+            }
+            "--analyse" => options.analyse = true,
+            flag if flag.starts_with("--") => return Err(format!("Unknown option {}", flag)),
+            other => options.filename = other.to_owned(),
+        }
+        i += 1;
+    }
+    options.modes = if is_custom {
+        vec![custom]
+    } else {
+        vec![WalkMode::single(), WalkMode::ghosts()]
+    };
+    for mode in options.modes.iter_mut() {
+        mode.simulate = simulate;
     }
-    let a = nums[0];
-    let b = lowest_common_multiple_of_set(&nums[1..]);
-    a * b / greatest_common_mult_pair(a, b)
+    Ok(options)
 }
 
-fn greatest_common_mult_pair(a: usize, b: usize) -> usize {
-    if b == 0 {
-        return a;
+fn main() {
+    // Usage: day8 [input] [--simulate [max steps]] [--start KIND VALUE] [--end KIND VALUE]
+    //        day8 [input] [--start KIND VALUE] [--end KIND VALUE] [--dot FILE] [--graphml FILE] [--analyse]
+    // KIND is exact, suffix or regex. Without --start/--end, both AAA -> ZZZ and ..A -> ..Z are solved
+    // Exports and analysis mark the ..A and ..Z nodes, or the custom ones if given
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let (mut network, instructions) = match read_file(&options.filename) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    if options.dot_file.is_some() || options.graphml_file.is_some() || options.analyse {
+        let mode = options.modes.last().unwrap();
+        network.set_matchers(&mode.start, &mode.end);
+        if let Some(dot_file) = &options.dot_file {
+            write(dot_file, graph::to_dot(&network)).unwrap();
+            println!("Wrote {}", dot_file);
+        }
+        if let Some(graphml_file) = &options.graphml_file {
+            write(graphml_file, graph::to_graphml(&network)).unwrap();
+            println!("Wrote {}", graphml_file);
+        }
+        if options.analyse {
+            graph::print_analysis(&network);
+        }
+        return;
+    }
+    for mode in &options.modes {
+        match solve(&mut network, &instructions, mode) {
+            Ok(line_results) => println!("{} Total {}", mode.name, line_results),
+            Err(e) => println!("{}: {}", mode.name, e),
+        }
     }
-    greatest_common_mult_pair(b, a % b)
 }
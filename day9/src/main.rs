@@ -1,7 +1,14 @@
+mod polynomial;
+mod stream;
+
+use num_bigint::BigInt;
+use polynomial::Polynomial;
 use std::{
-    collections::{HashMap, HashSet},
+    env, fmt,
     fs::read_to_string,
+    io::{self, BufRead},
 };
+use stream::StreamingSeries;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct NumberSeries {
@@ -16,16 +23,20 @@ impl NumberSeries {
     }
     pub fn from_slice(values: &[i64]) -> Self {
         Self {
-            number_set: Vec::from_iter(values.iter().map(|f| *f)),
+            number_set: values.to_vec(),
         }
     }
-    pub fn get_derivative(&self) -> NumberSeries {
-        let mut res = Vec::new();
-
-        for i in 1..self.number_set.len() {
-            res.push(self.number_set[i] - self.number_set[i - 1]);
-        }
-        Self::from_slice(&res)
+    /// None if any difference doesn't fit in an i64
+    pub fn checked_derivative(&self) -> Option<NumberSeries> {
+        let res: Option<Vec<i64>> = self
+            .number_set
+            .windows(2)
+            .map(|pair| pair[1].checked_sub(pair[0]))
+            .collect();
+        res.map(|values| Self::from_slice(&values))
+    }
+    pub fn is_empty(&self) -> bool {
+        self.number_set.is_empty()
     }
     pub fn is_zero(&self) -> bool {
         for v in &self.number_set {
@@ -43,47 +54,285 @@ impl NumberSeries {
     pub fn get_first(&self) -> i64 {
         *self.number_set.first().unwrap()
     }
+    pub fn fit_polynomial(&self) -> Polynomial {
+        Polynomial::fit(&self.number_set)
+    }
+    pub fn len(&self) -> usize {
+        self.number_set.len()
+    }
 }
-fn expand_set_return_last(set: &NumberSeries) -> i64 {
-    // println!("Set {:?}", set);
-    if set.is_zero() {
-        return 0;
-    }
-    let mut sub_set = set.get_derivative();
-    let last_number = expand_set_return_last(&mut sub_set);
-    return set.get_last() + last_number;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    /// Part 1, predict the values after the end
+    Forwards,
+    /// Part 2, predict the values before the start
+    Backwards,
 }
 
-fn prepend_set_return_last(set: &NumberSeries) -> i64 {
-    // println!("Set {:?}", set);
-    if set.is_zero() {
-        return 0;
-    }
-    let mut sub_set = set.get_derivative();
-    let last_number = prepend_set_return_last(&mut sub_set);
-    return set.get_first() - last_number;
+impl Direction {
+    fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "forwards" => Some(Direction::Forwards),
+            "backwards" => Some(Direction::Backwards),
+            _ => None,
+        }
+    }
 }
 
-fn read_file(filename: &str) -> i64 {
-    let file_contents = read_to_string(filename).unwrap();
-    let number_sets: Vec<NumberSeries> = file_contents
-        .lines()
-        .map(|l| NumberSeries::from_str(l))
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SeriesKind {
+    /// Every value is zero, the zero polynomial has no degree
+    Zero,
+    /// Differencing reached an all zero row that still had values in it
+    Polynomial(usize),
+    /// Differencing ran out of values first, so the only zero row is the empty one
+    NonPolynomial,
+    /// The differences at this level don't fit in an i64
+    Overflow(usize),
+}
+
+impl fmt::Display for SeriesKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SeriesKind::Zero => write!(f, "zero series"),
+            SeriesKind::Polynomial(degree) => write!(f, "polynomial of degree {}", degree),
+            SeriesKind::NonPolynomial => write!(f, "non-polynomial, differences never reach zero"),
+            SeriesKind::Overflow(level) => write!(f, "overflows i64 at difference level {}", level),
+        }
+    }
+}
+
+/// Difference the series down until a row is all zero, without trusting an empty row
+fn classify(set: &NumberSeries) -> SeriesKind {
+    let table = match build_difference_table(set) {
+        Ok(table) => table,
+        Err(kind) => return kind,
+    };
+    if table.last().unwrap().is_empty() {
+        SeriesKind::NonPolynomial
+    } else if table.len() == 1 {
+        SeriesKind::Zero
+    } else {
+        // The first zero row is one past the degree
+        SeriesKind::Polynomial(table.len() - 2)
+    }
+}
+
+/// Each row is the derivative of the one above, down to the first all zero row
+fn build_difference_table(set: &NumberSeries) -> Result<Vec<NumberSeries>, SeriesKind> {
+    let mut table = vec![set.clone()];
+    while !table.last().unwrap().is_zero() {
+        let next = table
+            .last()
+            .unwrap()
+            .checked_derivative()
+            .ok_or(SeriesKind::Overflow(table.len()))?;
+        table.push(next);
+    }
+    Ok(table)
+}
+
+/// Predict `count` values past the end (or before the start) of the series, nearest first
+/// Fails with the level that overflowed, either while differencing or growing the table back up
+fn extrapolate(
+    set: &NumberSeries,
+    direction: Direction,
+    count: usize,
+) -> Result<Vec<i64>, SeriesKind> {
+    // For each row we only need the edge value, updated as the table grows
+    let table = build_difference_table(set)?;
+    // A non-polynomial series ends on an empty row, which has no edge and holds the deepest row constant
+    let mut edges: Vec<i64> = table
+        .iter()
+        .filter(|row| !row.is_empty())
+        .map(|row| match direction {
+            Direction::Forwards => row.get_last(),
+            Direction::Backwards => row.get_first(),
+        })
         .collect();
+    let mut res = Vec::with_capacity(count);
+    for _ in 0..count {
+        // Walk back up the table, adding (or taking) the new value from below
+        for level in (0..edges.len() - 1).rev() {
+            edges[level] = match direction {
+                Direction::Forwards => edges[level].checked_add(edges[level + 1]),
+                Direction::Backwards => edges[level].checked_sub(edges[level + 1]),
+            }
+            .ok_or(SeriesKind::Overflow(level))?;
+        }
+        res.push(edges[0]);
+    }
+    Ok(res)
+}
 
-    let mut sum_added: i64 = 0;
-    for set in &number_sets {
-        // For each set, depth recurse to find 0's, then expand and go back up the stack
-        // let last_number = expand_set_return_last(set);
-        let last_number = prepend_set_return_last(set);
-        println!("Last {}", last_number);
-        sum_added += last_number;
-    }
-    sum_added
+fn read_file(filename: &str) -> Vec<NumberSeries> {
+    let file_contents = read_to_string(filename).unwrap();
+    file_contents.lines().map(NumberSeries::from_str).collect()
 }
 
 fn main() {
-    let line_results = read_file("input");
+    // Usage: day9 [forwards|backwards] [count]
+    //        day9 fit
+    //        day9 at <index>
+    //        day9 degrees
+    //        day9 stream [-]
+    // Without a direction both parts are run; count is how many values to predict
+    // fit prints each series' polynomial, at evaluates them all at an index (0 is the first value)
+    // stream feeds each series in one value at a time, with - it reads series live from stdin, one per line
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|s| s.as_str()) {
+        Some("fit") => {
+            for set in read_file("input") {
+                let polynomial = set.fit_polynomial();
+                // The fit has to agree with the difference table on the next value,
+                // when the table can work it out without overflowing i64
+                if let Ok(predicted) = extrapolate(&set, Direction::Forwards, 1) {
+                    let next = polynomial.evaluate_integer(&BigInt::from(set.len()));
+                    assert_eq!(next, BigInt::from(predicted[0]));
+                }
+                match polynomial.get_degree() {
+                    Some(degree) => println!("degree {}: p(x) = {}", degree, polynomial),
+                    None => println!("zero series: p(x) = 0"),
+                }
+            }
+            return;
+        }
+        Some("at") => {
+            let index: BigInt = args[1].parse().unwrap();
+            let sum: BigInt = read_file("input")
+                .iter()
+                .map(|set| set.fit_polynomial().evaluate_integer(&index))
+                .sum();
+            println!("Index {} Total {}", index, sum);
+            return;
+        }
+        Some("stream") if args.get(1).map(|s| s.as_str()) == Some("-") => {
+            for line in io::stdin().lock().lines() {
+                let mut series = StreamingSeries::new();
+                for value in line.unwrap().split_whitespace() {
+                    let value: i64 = match value.parse() {
+                        Ok(v) => v,
+                        Err(_) => {
+                            eprintln!("Skipping {}, not a number", value);
+                            continue;
+                        }
+                    };
+                    match series.push(value) {
+                        Ok(update) => {
+                            let next = match series.predict_next() {
+                                Some(next) => next.to_string(),
+                                None => "overflows i64".to_string(),
+                            };
+                            println!(
+                                "{} ({}), degree {}, next {}",
+                                value,
+                                update,
+                                series.get_degree().unwrap(),
+                                next
+                            );
+                        }
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            return;
+                        }
+                    }
+                }
+            }
+            return;
+        }
+        Some("stream") => {
+            let mut sum_added: Option<i64> = Some(0);
+            let mut unconfirmed = 0;
+            'series: for (i, set) in read_file("input").iter().enumerate() {
+                let mut series = StreamingSeries::new();
+                let mut breaks = 0;
+                for value in &set.number_set {
+                    match series.push(*value) {
+                        Ok(stream::Update::Broke { .. }) => breaks += 1,
+                        Ok(_) => {}
+                        Err(e) => {
+                            eprintln!("Warning: series {} {}, skipping it", i + 1, e);
+                            continue 'series;
+                        }
+                    }
+                }
+                let Some(next) = series.predict_next() else {
+                    eprintln!(
+                        "Warning: series {} prediction overflows i64, skipping it",
+                        i + 1
+                    );
+                    continue;
+                };
+                // Only the edges were kept, but they have to agree with the full table
+                if let Ok(predicted) = extrapolate(set, Direction::Forwards, 1) {
+                    assert_eq!(next, predicted[0]);
+                }
+                if !series.is_confirmed() {
+                    unconfirmed += 1;
+                }
+                if breaks > 0 {
+                    println!("Series {}: broke its pattern {} time(s)", i + 1, breaks);
+                }
+                sum_added = sum_added.and_then(|sum| sum.checked_add(next));
+            }
+            println!(
+                "{} series never held a pattern long enough to confirm it",
+                unconfirmed
+            );
+            match sum_added {
+                Some(sum) => println!("Streamed Total {}", sum),
+                None => println!("Streamed Total overflows i64"),
+            }
+            return;
+        }
+        Some("degrees") => {
+            let mut counts = [0; 3];
+            for (i, set) in read_file("input").iter().enumerate() {
+                let kind = classify(set);
+                counts[match kind {
+                    SeriesKind::Zero | SeriesKind::Polynomial(_) => 0,
+                    SeriesKind::NonPolynomial => 1,
+                    SeriesKind::Overflow(_) => 2,
+                }] += 1;
+                println!("Series {}: {}", i + 1, kind);
+            }
+            println!(
+                "{} polynomial, {} non-polynomial, {} overflowing",
+                counts[0], counts[1], counts[2]
+            );
+            return;
+        }
+        _ => {}
+    }
+    let directions = match args.first().and_then(|a| Direction::from_str(a)) {
+        Some(direction) => vec![direction],
+        None => vec![Direction::Forwards, Direction::Backwards],
+    };
+    let count: usize = args.iter().find_map(|a| a.parse().ok()).unwrap_or(1).max(1);
+    let number_sets = read_file("input");
+    // These give a meaningless answer, so warn about them
+    for (i, set) in number_sets.iter().enumerate() {
+        let kind = classify(set);
+        if kind == SeriesKind::NonPolynomial {
+            eprintln!("Warning: series {} is {}, predicting anyway", i + 1, kind);
+        }
+    }
 
-    println!("Total {}", line_results);
+    for direction in directions {
+        let mut sum_added: Option<i64> = Some(0);
+        for (i, set) in number_sets.iter().enumerate() {
+            // For each set, build the difference table down to 0's, then grow it back up
+            match extrapolate(set, direction, count) {
+                Ok(predicted) => {
+                    sum_added =
+                        sum_added.and_then(|sum| sum.checked_add(*predicted.last().unwrap()));
+                }
+                Err(kind) => eprintln!("Warning: series {} {}, skipping it", i + 1, kind),
+            }
+        }
+        match sum_added {
+            Some(sum) => println!("{:?} {} step(s) Total {}", direction, count, sum),
+            None => println!("{:?} {} step(s) Total overflows i64", direction, count),
+        }
+    }
 }
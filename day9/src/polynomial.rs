@@ -0,0 +1,101 @@
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+use std::fmt;
+
+/// Exact polynomial through a series, where x = 0 is the first value
+/// Coefficients are lowest power first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polynomial {
+    coefficients: Vec<BigRational>,
+}
+
+/// First value of each row of the difference table, Δ^k f(0), until a row is all zero
+/// Worked in place on one buffer rather than building every row
+fn leading_differences(values: &[i64]) -> Vec<BigInt> {
+    let mut row: Vec<BigInt> = values.iter().map(|v| BigInt::from(*v)).collect();
+    let mut res = Vec::new();
+    while !row.is_empty() && !row.iter().all(|v| v.is_zero()) {
+        res.push(row[0].clone());
+        for i in 0..row.len() - 1 {
+            row[i] = &row[i + 1] - &row[i];
+        }
+        row.pop();
+    }
+    res
+}
+
+impl Polynomial {
+    /// Newton's forward difference form, f(x) = sum of Δ^k f(0) * C(x, k), expanded into powers of x
+    pub fn fit(values: &[i64]) -> Self {
+        let mut coefficients: Vec<BigRational> = Vec::new();
+        // C(x, k) as a polynomial, starting from C(x, 0) = 1
+        let mut binomial: Vec<BigRational> = vec![BigRational::one()];
+        for (k, difference) in leading_differences(values).iter().enumerate() {
+            if k > 0 {
+                // C(x, k) = C(x, k - 1) * (x - (k - 1)) / k
+                let shift = BigRational::from_integer(BigInt::from(k - 1));
+                let divisor = BigRational::from_integer(BigInt::from(k));
+                let mut next = vec![BigRational::zero(); binomial.len() + 1];
+                for (power, coefficient) in binomial.iter().enumerate() {
+                    next[power + 1] += coefficient / &divisor;
+                    next[power] -= coefficient * &shift / &divisor;
+                }
+                binomial = next;
+            }
+            coefficients.resize(binomial.len(), BigRational::zero());
+            let scale = BigRational::from_integer(difference.clone());
+            for (power, coefficient) in binomial.iter().enumerate() {
+                coefficients[power] += coefficient * &scale;
+            }
+        }
+        Self { coefficients }
+    }
+    /// Degree of the fitted polynomial, None for the zero polynomial
+    pub fn get_degree(&self) -> Option<usize> {
+        self.coefficients.iter().rposition(|c| !c.is_zero())
+    }
+    /// Value at any index, negative indexes are before the series started
+    pub fn evaluate(&self, x: &BigInt) -> BigRational {
+        let x = BigRational::from_integer(x.clone());
+        // Horner's method, highest power first
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(BigRational::zero(), |acc, c| acc * &x + c)
+    }
+    /// Fitted through integers at integer points, so it is always an integer at integer points
+    pub fn evaluate_integer(&self, x: &BigInt) -> BigInt {
+        self.evaluate(x).to_integer()
+    }
+}
+
+impl fmt::Display for Polynomial {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut wrote_term = false;
+        for (power, coefficient) in self.coefficients.iter().enumerate().rev() {
+            if coefficient.is_zero() {
+                continue;
+            }
+            let sign = if coefficient.is_negative() { "-" } else { "+" };
+            if wrote_term {
+                write!(f, " {} ", sign)?;
+            } else if coefficient.is_negative() {
+                write!(f, "-")?;
+            }
+            let magnitude = coefficient.abs();
+            match (power, magnitude.is_one()) {
+                (0, _) => write!(f, "{}", magnitude)?,
+                (1, true) => write!(f, "x")?,
+                (1, false) => write!(f, "{} x", magnitude)?,
+                (_, true) => write!(f, "x^{}", power)?,
+                (_, false) => write!(f, "{} x^{}", magnitude, power)?,
+            }
+            wrote_term = true;
+        }
+        if !wrote_term {
+            write!(f, "0")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt;
+
+/// What a new value did to the fitted pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Update {
+    /// Not enough values yet to be sure of the pattern
+    Learning,
+    /// The value was the one predicted
+    Matched,
+    /// The value wasn't predicted, so the table grew to fit it
+    Broke { expected: i64, actual: i64 },
+}
+
+impl fmt::Display for Update {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Update::Learning => write!(f, "learning"),
+            Update::Matched => write!(f, "matched"),
+            Update::Broke { expected, actual } => {
+                write!(f, "broke pattern, expected {} got {}", expected, actual)
+            }
+        }
+    }
+}
+
+/// A series fed one value at a time, keeping only the right edge of its difference table
+/// Each push is O(degree), the table only grows when a value breaks the pattern
+/// Rows below the deepest edge are all zero, so predictions match the full triangle
+#[derive(Debug, Clone, Default)]
+pub struct StreamingSeries {
+    /// Last value of each difference row, the deepest row is assumed constant
+    edges: Vec<i64>,
+    /// How many pushes in a row have left the row below the deepest at zero
+    /// A few zeros can be a coincidence, so the pattern only counts once this passes the degree
+    zero_run: usize,
+    count: usize,
+}
+
+impl StreamingSeries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn push(&mut self, value: i64) -> Result<Update, String> {
+        self.count += 1;
+        if self.edges.is_empty() {
+            self.edges.push(value);
+            return Ok(Update::Learning);
+        }
+        let expected = self
+            .predict_next()
+            .ok_or_else(|| format!("Prediction overflows i64 at value {}", self.count))?;
+        // Walk down the table, each new difference is the one above less the old edge
+        let mut difference = value;
+        for edge in self.edges.iter_mut() {
+            let below = difference
+                .checked_sub(*edge)
+                .ok_or_else(|| format!("Difference overflows i64 at value {}", self.count))?;
+            *edge = difference;
+            difference = below;
+        }
+        let was_confirmed = self.is_confirmed();
+        if difference == 0 {
+            self.zero_run += 1;
+            return Ok(if was_confirmed {
+                Update::Matched
+            } else {
+                Update::Learning
+            });
+        }
+        // Every row below the deepest was all zero, so the difference we just found
+        // runs down the whole rest of the triangle, one row per earlier zero
+        let new_rows = self.count - self.edges.len();
+        self.edges.extend(std::iter::repeat_n(difference, new_rows));
+        self.zero_run = 0;
+        Ok(if was_confirmed {
+            Update::Broke {
+                expected,
+                actual: value,
+            }
+        } else {
+            Update::Learning
+        })
+    }
+    /// None until the first value arrives
+    pub fn predict_next(&self) -> Option<i64> {
+        if self.edges.is_empty() {
+            return None;
+        }
+        self.edges
+            .iter()
+            .try_fold(0i64, |acc, e| acc.checked_add(*e))
+    }
+    /// Degree of the pattern seen so far, only trustworthy once it is confirmed
+    pub fn get_degree(&self) -> Option<usize> {
+        self.edges.len().checked_sub(1)
+    }
+    /// The zero row has held for more values than it took to fit the pattern, degree + 2,
+    /// so even a constant needs three equal values before a change counts as a break
+    pub fn is_confirmed(&self) -> bool {
+        !self.edges.is_empty() && self.zero_run > self.edges.len()
+    }
+}
@@ -0,0 +1,128 @@
+//! GCD, LCM and modular arithmetic shared between the days
+//! Everything here either can't overflow or says so by returning None
+
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+pub fn gcd(a: u64, b: u64) -> u64 {
+    gcd_u128(a as u128, b as u128) as u64
+}
+
+pub fn gcd_u128(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+pub fn gcd_big(a: &BigUint, b: &BigUint) -> BigUint {
+    a.gcd(b)
+}
+
+/// None if the result doesn't fit in a u64
+pub fn checked_lcm(a: u64, b: u64) -> Option<u64> {
+    checked_lcm_u128(a as u128, b as u128).and_then(|l| u64::try_from(l).ok())
+}
+
+/// None if the result doesn't fit in a u128
+pub fn checked_lcm_u128(a: u128, b: u128) -> Option<u128> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    // Divide first so the only multiply is the one that has to happen
+    (a / gcd_u128(a, b)).checked_mul(b)
+}
+
+pub fn lcm_big(a: &BigUint, b: &BigUint) -> BigUint {
+    a.lcm(b)
+}
+
+/// LCM of every number, None for an empty set
+/// Works in u128 while it fits, then carries on in arbitrary precision
+pub fn lowest_common_multiple_of_set(nums: &[u64]) -> Option<BigUint> {
+    let (first, rest) = nums.split_first()?;
+    let mut small = *first as u128;
+    for (i, num) in rest.iter().enumerate() {
+        match checked_lcm_u128(small, *num as u128) {
+            Some(lcm) => small = lcm,
+            None => {
+                let mut big = BigUint::from(small);
+                for num in &rest[i..] {
+                    big = lcm_big(&big, &BigUint::from(*num));
+                }
+                return Some(big);
+            }
+        }
+    }
+    Some(BigUint::from(small))
+}
+
+/// Returns (g, x, y) with a*x + b*y = g = gcd(a, b)
+/// None if a step overflows, which takes i128::MIN as an input
+pub fn extended_gcd(a: i128, b: i128) -> Option<(i128, i128, i128)> {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1i128, 0i128);
+    let (mut old_y, mut y) = (0i128, 1i128);
+    while r != 0 {
+        let quotient = old_r.checked_div(r)?;
+        (old_r, r) = (r, old_r.checked_sub(quotient.checked_mul(r)?)?);
+        (old_x, x) = (x, old_x.checked_sub(quotient.checked_mul(x)?)?);
+        (old_y, y) = (y, old_y.checked_sub(quotient.checked_mul(y)?)?);
+    }
+    if old_r < 0 {
+        Some((
+            old_r.checked_neg()?,
+            old_x.checked_neg()?,
+            old_y.checked_neg()?,
+        ))
+    } else {
+        Some((old_r, old_x, old_y))
+    }
+}
+
+pub fn extended_gcd_big(a: &BigInt, b: &BigInt) -> (BigInt, BigInt, BigInt) {
+    let res = a.extended_gcd(b);
+    (res.gcd, res.x, res.y)
+}
+
+/// x such that a*x = 1 (mod m), if a and m are coprime
+pub fn mod_inverse(a: i128, m: i128) -> Option<i128> {
+    if m <= 0 {
+        return None;
+    }
+    let (g, x, _) = extended_gcd(a.rem_euclid(m), m)?;
+    if g != 1 {
+        return None;
+    }
+    Some(x.rem_euclid(m))
+}
+
+pub fn mod_inverse_big(a: &BigInt, m: &BigInt) -> Option<BigInt> {
+    if *m <= BigInt::zero() {
+        return None;
+    }
+    let (g, x, _) = extended_gcd_big(&a.mod_floor(m), m);
+    if !g.is_one() {
+        return None;
+    }
+    Some(x.mod_floor(m))
+}
+
+/// Merge t = a1 (mod m1) and t = a2 (mod m2) into one congruence t = a (mod lcm(m1, m2))
+/// The moduli need not be coprime, None if the two can never both hold
+pub fn combine_congruences(
+    a1: &BigInt,
+    m1: &BigInt,
+    a2: &BigInt,
+    m2: &BigInt,
+) -> Option<(BigInt, BigInt)> {
+    let (g, p, _) = extended_gcd_big(m1, m2);
+    let difference = a2 - a1;
+    if !(&difference % &g).is_zero() {
+        return None;
+    }
+    let lcm = m1 / &g * m2;
+    let k = (difference / &g * p).mod_floor(&(m2 / &g));
+    Some(((a1 + m1 * k).mod_floor(&lcm), lcm))
+}